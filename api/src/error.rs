@@ -12,6 +12,8 @@ pub enum Error {
     Deserialisation(String),
     #[error("no such font family: {0}")]
     NoSuchFamily(String),
+    #[error("integrity check failed: {0}")]
+    Integrity(String),
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -183,6 +183,15 @@ pub struct FontVariantSpec {
     pub style: FontStyle
 }
 
+impl From<DefinedFontVariantSpec> for FontVariantSpec {
+    fn from(value: DefinedFontVariantSpec) -> Self {
+        FontVariantSpec {
+            weight: FontWeight::Defined(value.weight),
+            style: FontStyle::Defined(value.style)
+        }
+    }
+}
+
 impl FontVariantSpec {
     pub fn is_covered_by(&self, other: &FontVariantSpec) -> bool {
         let weight_is_covered = self.weight.is_covered_by(&other.weight);
@@ -191,36 +200,66 @@ impl FontVariantSpec {
     }
 }
 
+/// A part of a font that only covers some scripts (e.g. `latin` or `cyrillic`).
+#[derive(Eq, PartialEq, Clone, Debug, Hash)]
+pub struct FontSubset {
+    pub name: String,
+    /// The characters in the subset, as a CSS `unicode-range` value.
+    pub unicode_range: Option<String>
+}
+impl FontSubset {
+    pub fn new(name: impl ToString, unicode_range: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            unicode_range
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
 pub struct DefinedFontInstallSpec {
     pub id: String,
-    pub styles: Vec<DefinedFontVariantSpec>
+    pub styles: Vec<DefinedFontVariantSpec>,
+    /// The subsets to download instead of the full fonts. If this is empty, the full fonts are downloaded.
+    pub subsets: Vec<FontSubset>
 }
 impl DefinedFontInstallSpec {
     pub fn new<S, I, F>(id: S, styles: I) -> Self where S: ToString, I: IntoIterator<Item = F>, F: Into<DefinedFontVariantSpec> {
         Self {
             id: id.to_string(),
-            styles: styles.into_iter().map(|v| v.into()).collect()
+            styles: styles.into_iter().map(|v| v.into()).collect(),
+            subsets: Vec::new()
         }
     }
+    pub fn with_subsets(mut self, subsets: impl IntoIterator<Item = FontSubset>) -> Self {
+        self.subsets = subsets.into_iter().collect();
+        self
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
 pub struct FontInstallSpec {
     pub id: String,
-    pub styles: Vec<FontVariantSpec>
+    pub styles: Vec<FontVariantSpec>,
+    /// The names of the subsets to download instead of the full fonts, if the source supports them.
+    pub subsets: Vec<String>
 }
 
 impl FontInstallSpec {
     pub fn new<S, I, F>(id: S, styles: I) -> Self where S: ToString, I: IntoIterator<Item = F>, F: Into<FontVariantSpec> {
         Self {
             id: id.to_string(),
-            styles: styles.into_iter().map(|v| v.into()).collect()
+            styles: styles.into_iter().map(|v| v.into()).collect(),
+            subsets: Vec::new()
         }
     }
     pub fn new_all_styles<S>(id: S) -> Self where S: ToString {
         Self::new(id, vec![FontVariantSpec { style: FontStyle::All, weight: FontWeight::All }])
     }
+    pub fn with_subsets<S>(mut self, subsets: impl IntoIterator<Item = S>) -> Self where S: ToString {
+        self.subsets = subsets.into_iter().map(|v| v.to_string()).collect();
+        self
+    }
 }
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
 pub struct FontDescription {
@@ -2,6 +2,9 @@ use std::path::PathBuf;
 use semver::Version;
 pub use toml;
 
+/// What [`FpmHost::max_concurrent_downloads`] is unless configured otherwise.
+pub const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 8;
+
 pub trait FpmHost: Sync {
     fn global_cache_dir(&self) -> PathBuf;
     fn cache_dir_for(&self, id: &str) -> PathBuf;
@@ -9,8 +12,15 @@ pub trait FpmHost: Sync {
     fn config(&self, id: String) -> Option<&toml::Value>;
     fn version(&self) -> Version;
     fn user_agent(&self) -> String;
+    /// How many files may be downloaded at the same time, at most (always at least 1).
+    /// Sources should share this limit between every font they download.
+    fn max_concurrent_downloads(&self) -> usize {
+        DEFAULT_MAX_CONCURRENT_DOWNLOADS
+    }
 }
 
+/// A placeholder host for sources that haven't been given one yet.
+/// Every method except [`FpmHost::user_agent`] panics, so tests should use `TempFpmHost` from `fontpm-testing` instead.
 #[derive(Copy, Clone)]
 pub struct EmptyFpmHost;
 impl EmptyFpmHost {
@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::Path;
+use sha2::{Digest, Sha256};
+use crate::Error;
+
+/// What a downloaded file is expected to be, as far as its source knows.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Hash)]
+pub struct FileIntegrity {
+    /// The size in bytes.
+    pub size: Option<u64>,
+    /// The SHA-256 digest, as hex.
+    pub sha256: Option<String>
+}
+
+impl FileIntegrity {
+    pub fn new(size: Option<u64>, sha256: Option<String>) -> Self {
+        Self { size, sha256 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size.is_none() && self.sha256.is_none()
+    }
+
+    /// Checks the file at `path`, failing with an [`Error::Integrity`] that names the file if it doesn't match.
+    pub fn verify(&self, path: &Path) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(())
+        }
+        self.verify_bytes(&path.display().to_string(), &fs::read(path)?)
+    }
+
+    /// Like [`FileIntegrity::verify`], but for a file that is still in memory. `name` is only used in the error.
+    pub fn verify_bytes(&self, name: &str, bytes: &[u8]) -> Result<(), Error> {
+        if let Some(size) = self.size {
+            if bytes.len() as u64 != size {
+                return Err(Error::Integrity(format!("{} is {} bytes instead of {}", name, bytes.len(), size)))
+            }
+        }
+        if let Some(expected) = &self.sha256 {
+            let actual = format!("{:x}", Sha256::digest(bytes));
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(Error::Integrity(format!("{} has the SHA-256 digest {} instead of {}", name, actual, expected)))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_size_and_digest() {
+        // SHA-256 of "font"
+        let digest = "795ea3efa43d0872b63bf0067be97553b46983e4f075097669391e9d15388ecc";
+        assert!(FileIntegrity::default().verify_bytes("a.ttf", b"anything").is_ok());
+        assert!(FileIntegrity::new(Some(4), Some(digest.to_string())).verify_bytes("a.ttf", b"font").is_ok());
+        assert!(FileIntegrity::new(None, Some(digest.to_uppercase())).verify_bytes("a.ttf", b"font").is_ok());
+
+        let error = FileIntegrity::new(Some(5), None).verify_bytes("a.ttf", b"font").unwrap_err();
+        assert!(matches!(error, Error::Integrity(ref message) if message.contains("a.ttf")));
+        let error = FileIntegrity::new(None, Some(digest.to_string())).verify_bytes("b.ttf", b"<html>").unwrap_err();
+        assert!(matches!(error, Error::Integrity(ref message) if message.contains("b.ttf")));
+    }
+}
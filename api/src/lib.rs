@@ -4,6 +4,8 @@ pub mod util;
 pub mod output;
 pub mod error;
 pub mod font;
+pub mod registry;
+pub mod integrity;
 
 pub use error::{Error, Result};
 pub use async_trait;
@@ -27,11 +27,21 @@ pub trait CliOutput: Sync {
 
 static mut IMPL: Option<&dyn CliOutput> = None;
 
-const MISSING_IMPL_MESSAGE: &str = "Log implementation is not set";
+/// Used when no implementation is set, e.g. when FontPM is used as a library.
+struct NoOutput;
+impl CliOutput for NoOutput {
+    fn is_enabled(&self, _kind: OutputKind) -> bool {
+        false
+    }
+    fn log(&self, _record: OutputRecord) -> Result<(), Error> {
+        Ok(())
+    }
+}
 
+/// Gets the output implementation, which discards everything if none has been set.
 pub fn get_impl() -> &'static dyn CliOutput {
     unsafe {
-        return IMPL.expect(MISSING_IMPL_MESSAGE);
+        IMPL.unwrap_or(&NoOutput)
     }
 }
 
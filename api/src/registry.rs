@@ -0,0 +1,207 @@
+use crate::host::FpmHost;
+use crate::source::Source;
+use crate::Error;
+pub use inventory;
+
+/// Constructs a source for a given host.
+pub type SourceConstructor = for<'host> fn(&'host dyn FpmHost) -> Box<dyn Source<'host> + 'host>;
+/// Constructs an instance of a [`SourceType`] with the ID it was configured under, for a given host.
+pub type SourceTypeConstructor = for<'host> fn(&str, &'host dyn FpmHost) -> Box<dyn Source<'host> + 'host>;
+
+/// Describes a source that can be created at runtime.
+///
+/// Source crates register a factory using [`register_source!`](crate::register_source),
+/// and hosts (such as the CLI) can then look them up using [`factories`] and [`find_factory`].
+#[derive(Copy, Clone)]
+pub struct SourceFactory {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Whether the source should be enabled in a freshly created configuration.
+    pub default_enabled: bool,
+    constructor: SourceConstructor
+}
+
+impl SourceFactory {
+    pub const fn new(id: &'static str, name: &'static str, default_enabled: bool, constructor: SourceConstructor) -> Self {
+        Self {
+            id,
+            name,
+            default_enabled,
+            constructor
+        }
+    }
+
+    pub fn create<'host>(&self, host: &'host dyn FpmHost) -> Box<dyn Source<'host> + 'host> {
+        (self.constructor)(host)
+    }
+}
+
+inventory::collect!(SourceFactory);
+
+/// Describes a kind of source that can be used any number of times under different IDs.
+///
+/// A source of a type is configured with a `[sources.<id>]` section whose `type` is the type's name,
+/// and is created with [`create_source`] like any other source.
+#[derive(Copy, Clone)]
+pub struct SourceType {
+    pub name: &'static str,
+    constructor: SourceTypeConstructor
+}
+
+impl SourceType {
+    pub const fn new(name: &'static str, constructor: SourceTypeConstructor) -> Self {
+        Self {
+            name,
+            constructor
+        }
+    }
+
+    pub fn create<'host>(&self, id: &str, host: &'host dyn FpmHost) -> Box<dyn Source<'host> + 'host> {
+        (self.constructor)(id, host)
+    }
+}
+
+inventory::collect!(SourceType);
+
+/// Registers a [`SourceFactory`] so that it shows up in [`factories`].
+///
+/// The expression must be usable in a constant context.
+///
+/// # Examples
+///
+/// ```ignore
+/// fontpm_api::register_source!(SourceFactory::new("my-source", "My Source", false, |host| {
+///     let mut source = MySource::new();
+///     source.set_host(host);
+///     Box::new(source)
+/// }));
+/// ```
+///
+/// Note that the crate containing the registration must be linked into the final binary,
+/// so a crate that isn't otherwise referenced needs a `use my_source_crate as _;` somewhere.
+#[macro_export]
+macro_rules! register_source {
+    ($factory:expr) => {
+        $crate::registry::inventory::submit! { $factory }
+    };
+}
+
+/// Registers a [`SourceType`] so that it can be found with [`find_source_type`].
+///
+/// The same rules as for [`register_source!`](crate::register_source) apply.
+#[macro_export]
+macro_rules! register_source_type {
+    ($source_type:expr) => {
+        $crate::registry::inventory::submit! { $source_type }
+    };
+}
+
+/// All registered source factories, sorted by ID.
+pub fn factories() -> Vec<&'static SourceFactory> {
+    let mut vec: Vec<&'static SourceFactory> = inventory::iter::<SourceFactory>.into_iter().collect();
+    vec.sort_by_key(|v| v.id);
+    vec
+}
+
+pub fn find_factory(id: &str) -> Option<&'static SourceFactory> {
+    inventory::iter::<SourceFactory>.into_iter().find(|v| v.id == id)
+}
+
+/// IDs of all registered sources that are enabled by default.
+pub fn default_sources() -> Vec<String> {
+    factories().into_iter()
+        .filter(|v| v.default_enabled)
+        .map(|v| v.id.to_string())
+        .collect()
+}
+
+pub fn find_source_type(name: &str) -> Option<&'static SourceType> {
+    inventory::iter::<SourceType>.into_iter().find(|v| v.name == name)
+}
+
+/// Creates the source `id`: the registered source with that ID,
+/// or else a source of the type named by `type` in its configuration.
+/// Returns `None` if neither exists, and an error if the configured type isn't registered.
+pub fn create_source<'host>(id: &str, host: &'host dyn FpmHost) -> Result<Option<Box<dyn Source<'host> + 'host>>, Error> {
+    if let Some(factory) = find_factory(id) {
+        return Ok(Some(factory.create(host)))
+    }
+    let type_name = match host.config(id.to_string()).and_then(|v| v.get("type")).and_then(|v| v.as_str()) {
+        Some(v) => v,
+        None => return Ok(None)
+    };
+    match find_source_type(type_name) {
+        Some(source_type) => Ok(Some(source_type.create(id, host))),
+        None => Err(Error::Generic(format!("source {} has the unknown type {}", id, type_name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use async_trait::async_trait;
+    use crate::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontDescription, FontInstallSpec};
+    use crate::host::EmptyFpmHost;
+    use crate::source::RefreshOutput;
+    use super::*;
+
+    struct TestSource(&'static str);
+
+    #[async_trait]
+    impl<'host> Source<'host> for TestSource {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn name(&self) -> &str {
+            "Test"
+        }
+
+        fn set_host(&mut self, _host: &'host dyn FpmHost) {}
+
+        async fn refresh(&self, _force_refresh: bool) -> Result<RefreshOutput, Error> {
+            Ok(RefreshOutput::AlreadyUpToDate)
+        }
+
+        async fn resolve_font(&self, _spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+            unimplemented!()
+        }
+
+        async fn download_font(&self, _spec: &DefinedFontInstallSpec, _dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+            unimplemented!()
+        }
+    }
+
+    // registered out of order, to check that they're sorted
+    crate::register_source!(SourceFactory::new("test-c", "Test C", true, |_| Box::new(TestSource("test-c"))));
+    crate::register_source!(SourceFactory::new("test-a", "Test A", true, |_| Box::new(TestSource("test-a"))));
+    crate::register_source!(SourceFactory::new("test-b", "Test B", false, |_| Box::new(TestSource("test-b"))));
+
+    #[test]
+    fn factories_are_sorted_by_id() {
+        let ids: Vec<_> = factories().into_iter().map(|v| v.id).collect();
+        assert_eq!(ids, vec!["test-a", "test-b", "test-c"]);
+    }
+
+    #[test]
+    fn find_factory_by_id() {
+        let factory = find_factory("test-b").unwrap();
+        assert_eq!(factory.name, "Test B");
+        assert!(!factory.default_enabled);
+        assert_eq!(factory.create(&EmptyFpmHost::EMPTY_HOST).id(), "test-b");
+
+        assert!(find_factory("test-d").is_none());
+    }
+
+    #[test]
+    fn default_sources_are_default_enabled() {
+        assert_eq!(default_sources(), vec!["test-a".to_string(), "test-c".to_string()]);
+    }
+
+    #[test]
+    fn create_registered_source() {
+        let source = create_source("test-a", &EmptyFpmHost::EMPTY_HOST).unwrap().unwrap();
+        assert_eq!(source.id(), "test-a");
+    }
+}
@@ -11,20 +11,82 @@ pub enum RefreshOutput {
     Downloaded
 }
 
+/// How a source's index changed when it was last downloaded.
+#[derive(Clone, Debug, Default)]
+pub struct IndexChanges {
+    pub added: Vec<FontDescription>,
+    pub removed: Vec<FontDescription>,
+    pub updated: Vec<FontUpdate>
+}
+
+impl IndexChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// A font that is in both indices, but with a new version or new variants.
+#[derive(Clone, Debug)]
+pub struct FontUpdate {
+    pub font: FontDescription,
+    /// The version in the previous index, if the version changed.
+    pub previous_version: Option<String>,
+    pub added_variants: Vec<DefinedFontVariantSpec>
+}
+
+/// A font found by [`Source::search_by_tags`].
+#[derive(Clone, Debug)]
+pub struct TaggedFont {
+    pub font: FontDescription,
+    /// The total size of the files of every variant in bytes, if the source knows all of them.
+    pub size: Option<u64>
+}
+
 #[async_trait]
 pub trait Source<'host>: Send + Sync {
-    fn id(&self) -> &'host str;
-    fn name(&self) -> &'host str;
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
 
     fn set_host(&mut self, host: &'host dyn FpmHost);
 
     async fn refresh(&self, force_refresh: bool) -> Result<RefreshOutput, Error>;
+    /// Replaces the local index with `index`, which is in the same format [`Source::refresh`] downloads,
+    /// without contacting the source. The index should be validated before anything is replaced.
+    /// Sources without a local index don't support this.
+    async fn import_index(&self, _index: &[u8]) -> Result<(), Error> {
+        Err(Error::Generic(format!("{} does not support importing an index", self.name())))
+    }
+    /// The changes between the current index and the one it replaced,
+    /// or `None` if there is no previous index or the source doesn't keep it.
+    async fn changes(&self) -> Result<Option<IndexChanges>, Error> {
+        Ok(None)
+    }
     async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error>;
     async fn download_font(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error>;
+    /// Downloads a file for every variant and subset in `spec`, which is only called if [`Source::resolve_font`] returned subsets.
+    /// The files are keyed by subset name.
+    async fn download_subsets(&self, _spec: &DefinedFontInstallSpec, _dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>>, Error> {
+        Err(Error::Generic(format!("{} does not support subsets", self.name())))
+    }
+    /// Downloads the license documents of the font in `spec` (e.g. `OFL.txt`), keyed by file name.
+    /// Sources that don't have licenses return none.
+    async fn download_licenses(&self, _spec: &DefinedFontInstallSpec, _dir: &PathBuf) -> Result<HashMap<String, PathBuf>, Error> {
+        Ok(HashMap::new())
+    }
+    /// Searches the source for fonts matching `query`.
+    /// Sources that don't support searching return no results.
+    async fn search(&self, _query: &str) -> Result<Vec<FontDescription>, Error> {
+        Ok(Vec::new())
+    }
+    /// Finds the fonts that have every one of `tags` (e.g. `monospace`), sorted by ID.
+    /// Sources without tags return no results.
+    async fn search_by_tags(&self, _tags: &[String]) -> Result<Vec<TaggedFont>, Error> {
+        Ok(Vec::new())
+    }
     fn description(&self) -> SourceDescription {
         SourceDescription {
             id: self.id().to_string(),
-            name: self.id().to_string()
+            name: self.name().to_string()
         }
     }
 }
@@ -1,9 +1,8 @@
-pub fn sources() -> Vec<String> {
-    let mut vec: Vec<String> = vec!();
-
-    // Now here comes the fun part!
-    #[cfg(feature = "google-fonts")]
-    vec.push(fontpm_source_google_fonts::GoogleFontsSource::ID.into());
-
-    vec
-}
\ No newline at end of file
+// Built-in sources register themselves with `fontpm_api::registry`,
+// but each crate still has to be linked in for its registration to be picked up.
+#[cfg(feature = "google-fonts")]
+use fontpm_source_google_fonts as _;
+#[cfg(feature = "local")]
+use fontpm_source_local as _;
+#[cfg(feature = "registry")]
+use fontpm_source_registry as _;
@@ -2,12 +2,12 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use clap::{ArgMatches, Command};
 use fontpm_api::collection;
-use crate::generate;
 
 mod refresh;
 mod install;
 mod config;
 mod purge;
+mod changes;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -19,12 +19,15 @@ pub enum Error {
     API(#[from] fontpm_api::Error),
     #[error("join error: {0}")]
     Join(#[from] tokio::task::JoinError),
-    #[error("generation failed: {0}")]
-    Generate(#[from] generate::GenerateError),
+    #[error("{0}")]
+    Core(#[from] fontpm_core::Error),
     #[error("invalid argument(s): {0}")]
     ArgMatch(#[from] clap::Error),
     #[error("{0}")]
-    ConfirmationNeeded(String)
+    ConfirmationNeeded(String),
+    /// Some of the work succeeded. Exits with [`EXIT_PARTIAL_FAILURE`].
+    #[error("{0}")]
+    PartialFailure(String)
 
 }
 impl From<std::io::Error> for Error {
@@ -33,6 +36,11 @@ impl From<std::io::Error> for Error {
     }
 }
 
+/// The exit code when a command fails.
+pub const EXIT_FAILURE: i32 = 1;
+/// The exit code when a command only partially succeeds.
+pub const EXIT_PARTIAL_FAILURE: i32 = 2;
+
 pub type Result = std::result::Result<Option<String>, Error>;
 pub struct CommandAndRunner {
     pub description: Command,
@@ -65,6 +73,7 @@ pub fn all_commands() -> HashMap<String, CommandAndRunner> {
         config::NAME => config::command(),
         refresh::NAME => refresh::command(),
         install::NAME => install::command(),
-        purge::NAME => purge::command()
+        purge::NAME => purge::command(),
+        changes::NAME => changes::command()
     };
 }
\ No newline at end of file
@@ -0,0 +1,96 @@
+use std::path::Path;
+use clap::Command;
+use fontpm_api::{error, info, Source};
+use fontpm_api::source::IndexChanges;
+use fontpm_api::util::plural_s;
+use fontpm_core::config::FpmConfig;
+use crate::commands::{CommandAndRunner, Error};
+use fontpm_core::host::FpmHostImpl;
+use crate::runner;
+use crate::sources::create_sources;
+
+pub const NAME: &str = "changes";
+
+/// Lists `changes`, marking the fonts that are installed in `install_dir`.
+fn table(changes: &IndexChanges, install_dir: &Path) -> String {
+    let installed = |id: &str| if install_dir.join(id).is_dir() { "yes" } else { "" }.to_string();
+
+    let mut rows = vec![["CHANGE".to_string(), "FONT".to_string(), "INSTALLED".to_string(), "DETAILS".to_string()]];
+    for font in &changes.added {
+        rows.push(["added".into(), font.id.clone(), installed(&font.id), format!("{} (version {})", font.name, font.version)]);
+    }
+    for update in &changes.updated {
+        let mut details = Vec::new();
+        if let Some(previous) = &update.previous_version {
+            details.push(format!("version {} -> {}", previous, update.font.version));
+        }
+        if !update.added_variants.is_empty() {
+            let variants: Vec<String> = update.added_variants.iter().map(|v| format!("{} {}", v.weight, v.style)).collect();
+            details.push(format!("new variant{}: {}", plural_s(variants.len()), variants.join(", ")));
+        }
+        rows.push(["updated".into(), update.font.id.clone(), installed(&update.font.id), details.join("; ")]);
+    }
+    for font in &changes.removed {
+        rows.push(["removed".into(), font.id.clone(), installed(&font.id), font.name.clone()]);
+    }
+
+    let mut widths = [0; 3];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|[change, font, installed, details]| format!("  {:w0$}  {:w1$}  {:w2$}  {}", change, font, installed, details, w0 = widths[0], w1 = widths[1], w2 = widths[2]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Prints what changed in the index of `source` when it was last refreshed.
+pub async fn print_changes(source: &dyn Source<'_>, install_dir: &Path) -> Result<(), Error> {
+    match source.changes().await? {
+        None => {
+            info!("[{}] There is no previous index to compare with.", source.name());
+        },
+        Some(changes) if changes.is_empty() => {
+            info!("[{}] No fonts changed since the previous index.", source.name());
+        },
+        Some(changes) => {
+            let installed = changes.added.iter()
+                .chain(&changes.removed)
+                .chain(changes.updated.iter().map(|v| &v.font))
+                .filter(|v| install_dir.join(&v.id).is_dir())
+                .count();
+            info!("[{}] Changes since the previous index ({} installed):\n{}", source.name(), installed, table(&changes, install_dir));
+        }
+    }
+    Ok(())
+}
+
+runner! { _args =>
+    let host = FpmHostImpl::create(None)?;
+    let sources = create_sources(&host, None)?;
+    let install_dir = FpmConfig::load()?.font_install_dir();
+
+    let mut errored = 0;
+    for source in &sources {
+        if let Err(e) = print_changes(source.as_ref(), &install_dir).await {
+            errored += 1;
+            error!("[{}] Could not list changes: {}", source.name(), e);
+        }
+    }
+
+    if errored > 0 {
+        Err(Error::Custom(format!("{} source{} failed to list changes", errored, plural_s(errored))))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn command() -> CommandAndRunner {
+    CommandAndRunner {
+        description: Command::new(NAME)
+            .about("List the fonts that changed in the last refresh of each source's index"),
+        runner: Box::new(runner)
+    }
+}
@@ -1,7 +1,7 @@
 use clap::{arg, ArgAction, ArgMatches, Command, Subcommand, FromArgMatches};
 use fontpm_api::{info, ok, Error as FError};
 use crate::commands::{CommandAndRunner, Error};
-use crate::config::{EntireConfig, FpmConfig};
+use fontpm_core::config::{EntireConfig, FpmConfig};
 use crate::runner;
 
 pub const NAME: &str = "config";
@@ -71,6 +71,9 @@ runner! { master_args =>
                         let path: &::std::path::Path = value.as_ref();
                         format!("{}", path.display())
                     }};
+                    (number $value:expr;) => {
+                        format!("{}", $value)
+                    };
                     (string $value:expr;) => {
                         format!("\"{}\"", $value)
                     };
@@ -87,6 +90,8 @@ runner! { master_args =>
                 config_write!("fontpm.enabled_sources" => array:string config.enabled_sources);
                 config_write!("fontpm.cache_dir" => option:path config.cache_dir; default path config.cache_dir(););
                 config_write!("fontpm.font_install_dir" => option:path config.font_install_dir; default path config.font_install_dir(););
+                config_write!("fontpm.plugin_dir" => option:path config.plugin_dir; default path config.plugin_dir(););
+                config_write!("fontpm.max_concurrent_downloads" => option:number config.max_concurrent_downloads; default number config.max_concurrent_downloads(););
             }
 
             Ok(None)
@@ -1,20 +1,13 @@
-use std::collections::HashMap;
-use std::fs::{copy, create_dir_all};
-use std::mem::{replace};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use clap::{arg, ArgAction, ArgMatches, Command, value_parser};
-use clap::builder::ArgPredicate;
-use multimap::MultiMap;
-use path_clean::PathClean;
-use fontpm_api::{error, FpmHost, info, ok, Source, trace, warning};
-use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription, FontInstallSpec};
+use fontpm_api::{error, info, ok, trace, warning};
 use fontpm_api::util::{nice_list, plural_s, plural_s_opposite};
+use fontpm_core::{FontSelector, FontSpec, InstallEvent, Installer, InstallReport, Layout, ResolvedFont, SelectedFont};
 use crate::commands::{CommandAndRunner, Error};
-use crate::config::FpmConfig;
-use crate::generate::Generate;
-use crate::host_impl::FpmHostImpl;
+use fontpm_core::config::FpmConfig;
+use fontpm_core::host::FpmHostImpl;
 use crate::runner;
-use crate::sources::{create_sources, FontSpec};
+use crate::sources::create_sources;
 
 pub const NAME: &str = "install";
 
@@ -25,41 +18,11 @@ enum OutputFormat {
     Flat,
     FlatDirectory,
 }
-impl OutputFormat {
-    fn get_path(&self, base_dir: impl AsRef<Path>, font_spec: &DefinedFontInstallSpec, variant_spec: &DefinedFontVariantSpec, source_path: impl AsRef<Path>) -> PathBuf {
-        let base_dir = base_dir.as_ref();
-        let source_path = source_path.as_ref();
-        let ext = source_path.extension().map(|v| String::from(".") + v.to_str().unwrap()).unwrap_or("".to_string());
-        let file_name = format!("{}{}{}", font_spec.id, {
-            if variant_spec == &DefinedFontVariantSpec::REGULAR {
-                "-regular".to_string()
-            } else {
-                let style = match variant_spec.style {
-                    DefinedFontStyle::Regular => "",
-                    DefinedFontStyle::Italic => "-italic",
-                };
-                match variant_spec.weight {
-                    DefinedFontWeight::REGULAR => style.to_string(),
-                    DefinedFontWeight::Fixed(weight) => "-".to_string() + weight.to_string().as_str() + style,
-                    DefinedFontWeight::Variable => "-wght".to_string() + style
-                }
-            }
-        }, ext);
-        match self {
-            Self::Flat => {
-                base_dir.join(file_name)
-            }
-            Self::FlatDirectory => {
-                base_dir.join(&font_spec.id).join(file_name)
-            }
-        }
-    }
-    fn get_misc_path(&self, base_dir: impl AsRef<Path>, font_desc: &FontDescription, name: impl AsRef<Path>) -> PathBuf {
-        let base_dir = base_dir.as_ref();
-        let name = name.as_ref();
-        match self {
-            Self::Flat => base_dir.join(name),
-            Self::FlatDirectory => base_dir.join(&font_desc.id).join(name)
+impl From<&OutputFormat> for Layout {
+    fn from(value: &OutputFormat) -> Self {
+        match value {
+            OutputFormat::Flat => Layout::Flat,
+            OutputFormat::FlatDirectory => Layout::FlatDirectory
         }
     }
 }
@@ -77,10 +40,28 @@ async fn _runner(args: &ArgMatches) -> Result<Option<String>, Error> {
         return Err(Error::Custom("At least one fontspec must be provided".into()));
     }
 
+    let (selectors, fontspecs): (Vec<&String>, Vec<&String>) = fontspecs.partition(|v| FontSelector::is_selector(v));
+    let selectors: Vec<(String, fontpm_api::Result<FontSelector>)> = selectors.into_iter()
+        .map(|v| (v.clone(), FontSelector::parse(v)))
+        .collect();
     let fontspecs: Vec<(String, fontpm_api::Result<FontSpec>)> = fontspecs.into_iter()
         .map(|v| (v.clone(), FontSpec::parse(v)))
         .collect();
 
+    let selectors = {
+        let mut vec: Vec<FontSelector> = Vec::new();
+        for (original, result) in selectors.iter() {
+            match result {
+                Err(error) => {
+                    warning!("Error whilst parsing selector \"{}\": {}", original, error);
+                },
+                Ok(selector) => {
+                    vec.push(selector.clone());
+                }
+            }
+        }
+        (vec, selectors.len())
+    };
     let fontspecs = {
         let mut vec: Vec<FontSpec> = Vec::new();
         for item in fontspecs.iter() {
@@ -95,10 +76,10 @@ async fn _runner(args: &ArgMatches) -> Result<Option<String>, Error> {
                 }
             }
         }
-        (vec, fontspecs.len())
+        (vec, fontspecs.len() + selectors.1)
     };
 
-    if fontspecs.0.len() < 1 {
+    if fontspecs.0.len() + selectors.0.len() < 1 {
         return Err(Error::Custom(format!(
             "{}. Perhaps you made a typo?",
             if fontspecs.1 == 1 {
@@ -110,158 +91,198 @@ async fn _runner(args: &ArgMatches) -> Result<Option<String>, Error> {
     }
 
     let fontspecs = fontspecs.0;
+    let selectors = selectors.0;
     // TODO: Make sure no two fontspecs conflict
 
-    let required_sources = if fontspecs.iter().any(|v| v.source.is_none()) {
+    let required_sources = if fontspecs.iter().map(|v| &v.source).chain(selectors.iter().map(|v| &v.source)).any(Option::is_none) {
         None
     } else {
         Some(
             fontspecs.iter()
                 .map(|v| v.source.as_ref().unwrap())
+                .chain(selectors.iter().map(|v| v.source.as_ref().unwrap()))
                 .collect()
         )
     };
 
     let host = FpmHostImpl::create(Some(config.font_install_dir()))?;
-    let sources = create_sources(Some(&host), required_sources.clone())?;
+    let sources = create_sources(&host, required_sources.clone())?;
 
     if sources.is_empty() {
-        if let Some(only) = required_sources {
+        return Err(Error::Custom(if let Some(only) = required_sources {
             let s = plural_s(only.len());
             let s_opposite = plural_s_opposite(only.len());
-            error!("No source{} with the ID{} {} exist{} (perhaps you have the source{} disabled?)",
+            format!("No source{} with the ID{} {} exist{} (perhaps you have the source{} disabled?)",
                 s,
                 s,
                 nice_list(only.clone(), "and"),
                 s_opposite,
                 s
-            );
+            )
         } else {
-            error!("No sources are enabled. Please enable sources in your configuration file.");
-        }
+            "No sources are enabled. Please enable sources in your configuration file.".into()
+        }));
     }
 
-    let mut fontspec_by_source = MultiMap::new();
-    for fontspec in &fontspecs {
-        fontspec_by_source.insert(fontspec.source.clone(), fontspec.clone());
+    let mut installer = Installer::new(&host)
+        .sources(sources)
+        .observe(|event| match event {
+            InstallEvent::Resolving { source } => {
+                trace!("Running on source {}", source.id);
+            },
+            InstallEvent::Installing { font } => {
+                info!("Installing {} from {}", font.description.name, font.source.name);
+            },
+            InstallEvent::Copying { from, to } => {
+                trace!("Copying cache file {} to target path {}", from.display(), to.display());
+            },
+            InstallEvent::Skipped { font } => {
+                info!("Skipping {}: {}", font.font.spec, font.reason);
+            },
+            InstallEvent::Failed { failure } => {
+                error!("Could not install {}: {}", failure.spec, failure.error);
+            }
+        });
+    for fontspec in fontspecs {
+        installer = installer.add(fontspec);
     }
-
-    let mut resolved = HashMap::new();
-    let mut errors = false;
-    for source in fontspec_by_source.keys() {
-        // NOTE(tecc): `None` means "any source"
-        let fonts_to_download = fontspec_by_source.get_vec(source).unwrap();
-
-        let target_sources: Vec<_> = if let Some(source_name) = source {
-            sources.iter()
-                .filter(|v| v.name() == source_name)
-                .collect()
-        } else {
-            sources.iter().collect()
-        };
-
-        let mut resolved_from_source = HashMap::new();
-        for source in &target_sources {
-            trace!("Running on source {}", source.id());
-            let resolved = fonts_to_download.iter()
-                .map(|fontspec| async move {
-                    (fontspec.clone(), source.description(), source.resolve_font(&FontInstallSpec::new_all_styles(&fontspec.font_id)).await)
-                });
-            let resolved = futures::future::join_all(resolved).await;
-            for resolved in resolved {
-                if let Some((_, _, Ok(_))) = resolved_from_source.get(&resolved.0.font_id) {
-                    continue
+    if !selectors.is_empty() {
+        let mut selected: Vec<SelectedFont> = Vec::new();
+        for selector in &selectors {
+            let fonts = installer.expand(selector).await?;
+            if fonts.is_empty() {
+                return Err(Error::Custom(format!("No fonts match {}.", selector)));
+            }
+            for font in fonts {
+                if !selected.iter().any(|v| v.spec == font.spec) {
+                    selected.push(font);
                 }
-                resolved_from_source.insert(resolved.0.font_id.clone(), resolved);
             }
         }
 
-        for entry in resolved_from_source {
-            match entry.1.2 {
-                Ok(v) => {
-                    if !resolved.contains_key(&entry.0) {
-                        resolved.insert(entry.0, (entry.1.0, entry.1.1, v.clone()));
-                    }
-                },
-                Err(e) => {
-                    let source_name = if target_sources.len() > 1 {
-                        "any of the sources"
-                    } else {
-                        target_sources.first().unwrap().name()
-                    };
-                    error!("Could not resolve font {} from {}: {}", entry.0, source_name, e);
-                    errors = true;
-                }
-            }
+        for font in &selected {
+            info!("  {} - {} ({})", font.spec, font.font.name, font.size.map_or("unknown size".into(), format_size));
+        }
+        let fonts = format!("{} font{} ({})", selected.len(), plural_s(selected.len()), total_size(&selected));
+        if !args.get_flag("confirm") {
+            return Err(Error::ConfirmationNeeded(format!("Install {}? (Run this command with --confirm)", fonts)));
         }
-        // println!("{:?}", all_resolved);
+        info!("Installing {}", fonts);
+        for font in selected {
+            installer = installer.add(font.spec);
+        }
+    }
+    let keep_going = args.get_flag("keep-going");
+    installer = installer.keep_going(keep_going);
+    if let Some(subsets) = args.get_many::<String>("subset") {
+        installer = installer.subsets(subsets);
     }
-    if errors {
-        return Err(Error::Custom(format!("Some fonts failed to resolve.")));
+    if let Some(dir) = args.get_one::<PathBuf>("directory") {
+        installer = installer
+            .target(dir)
+            .layout(args.get_one::<OutputFormat>("format").unwrap_or(&OutputFormat::FlatDirectory).into())
+            .generate_css(args.get_flag("generate-css"));
     }
 
-    let (directory, output_format, generate_css) = match args.get_one::<PathBuf>("directory") {
-        None => (host.font_install_dir(), OutputFormat::FlatDirectory, false),
-        Some(dir) => (dir.clone(), args.get_one("format").unwrap_or(&OutputFormat::FlatDirectory).clone(), args.get_flag("generate-css"))
-    };
-    let directory = {
-        let mut dir = directory.clean();
-        if dir.is_relative() {
-            let mut pwd = std::env::current_dir()?;
-            pwd.push(dir);
-            dir = pwd;
-        }
-        dir
+    let report = match installer.run().await {
+        Ok(v) => v,
+        Err(fontpm_core::Error::Resolve(failures)) => {
+            for failure in failures {
+                let source_name = match failure.sources.as_slice() {
+                    [name] => name.as_str(),
+                    _ => "any of the sources"
+                };
+                error!("Could not resolve font {} from {}: {}", failure.spec.font_id, source_name, failure.error);
+            }
+            return Err(Error::Custom("Some fonts failed to resolve.".into()));
+        },
+        Err(e) => return Err(e.into())
     };
-    create_dir_all(&directory)?;
-    let sources: HashMap<_, _> = sources.into_iter().map(|v| (v.id().to_string(), v)).collect();
 
-    for resolved in &resolved {
-        let (_, (_font_spec, source_desc, (install_spec, font_desc))) = resolved;
-        let source = sources.get(&source_desc.id).expect("logic error");
-        info!("Installing {} from {}", font_desc.name, source_desc.name);
-        match source.download_font(&install_spec, &host.cache_dir_for(source.id())).await {
-            Ok(mut paths) => {
-                for (spec, path) in &mut paths {
-                    let target_path = output_format.get_path(&directory, &install_spec, &spec, &path) ;
-                    trace!("Copying cache file {} to target path {}", path.display(), target_path.display());
-                    if let Some(parent) = target_path.parent() {
-                        create_dir_all(parent)?;
-                    }
-                    copy(&path, &target_path)?;
-                    if generate_css {
-                        let _ = replace(path, target_path);
-                    }
-                }
-                if generate_css {
-                    let target = output_format.get_misc_path(&directory, &font_desc, format!("{}.css", font_desc.id));
-                    let generate = Generate::from_font(&target, &font_desc, paths);
-                    let generated = generate.generate_css()?;
-                    tokio::fs::write(&target, generated).await?;
-                }
-            },
-            Err(e) => {
-                return Err(Error::Custom(format!("Could not download font {} from {}: {}", font_desc.name, source_desc.name, e,)))
-            }
+    if keep_going {
+        info!("Summary:\n{}", summary(&report));
+        if !report.is_complete() {
+            return Err(if report.fonts.is_empty() {
+                Error::Custom("No fonts could be installed.".into())
+            } else {
+                Error::PartialFailure(format!("{} of {} fonts could not be installed.", report.failed.len(), report.fonts.len() + report.skipped.len() + report.failed.len()))
+            })
         }
-        // info!("Installed font!");
     }
 
-    // dbg!(resolved);
-
-    let fonts = match resolved.len() {
-        1 => format!("font {}", resolved.values().last().unwrap().2.1.name),
-        len => format!("{} fonts", len)
+    let mut sources: Vec<&str> = report.fonts.iter().map(|v| v.font.source.name.as_str()).collect();
+    sources.sort();
+    sources.dedup();
+    let fonts = match report.fonts.as_slice() {
+        [font] => format!("font {}", font.font.description.name),
+        fonts => format!("{} fonts", fonts.len())
     };
-    let sources = match sources.len() {
-        1 => sources.values().last().unwrap().name().to_string(),
-        len => format!("{} sources", len)
+    let sources = match sources.as_slice() {
+        [source] => source.to_string(),
+        sources => format!("{} sources", sources.len())
     };
     ok!("Successfully installed {} from {}!", fonts, sources);
 
     Ok(None)
 }
 
+/// Formats `bytes` like `1.5 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes)
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// The total size of `fonts`, which is a lower bound if the sizes of some of them aren't known.
+fn total_size(fonts: &[SelectedFont]) -> String {
+    let total = format_size(fonts.iter().filter_map(|v| v.size).sum());
+    match fonts.iter().filter(|v| v.size.is_none()).count() {
+        0 => total,
+        unknown if unknown == fonts.len() => "unknown size".into(),
+        _ => format!("at least {}", total)
+    }
+}
+
+/// Formats a table with a row for every installed, skipped and failed font.
+fn summary(report: &InstallReport) -> String {
+    fn font_name(font: &ResolvedFont) -> String {
+        format!("{} ({})", font.description.name, font.source.name)
+    }
+
+    let mut rows = vec![["STATUS".to_string(), "FONTSPEC".to_string(), "FONT".to_string(), "DETAILS".to_string()]];
+    for font in &report.fonts {
+        rows.push(["installed".into(), font.font.spec.to_string(), font_name(&font.font), {
+            let files = font.paths().count();
+            format!("{} file{}", files, plural_s(files))
+        }]);
+    }
+    for font in &report.skipped {
+        rows.push(["skipped".into(), font.font.spec.to_string(), font_name(&font.font), font.reason.to_string()]);
+    }
+    for failure in &report.failed {
+        rows.push(["failed".into(), failure.spec.to_string(), failure.font.as_ref().map_or("-".into(), font_name), failure.error.to_string()]);
+    }
+
+    let mut widths = [0; 3];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|[status, spec, font, details]| format!("  {:w0$}  {:w1$}  {:w2$}  {}", status, spec, font, details, w0 = widths[0], w1 = widths[1], w2 = widths[2]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 runner! { args => _runner(args).await }
 
 pub fn command() -> CommandAndRunner {
@@ -273,7 +294,9 @@ pub fn command() -> CommandAndRunner {
                     .long_help(
 "Specify the fonts to install.
 You can either specify it as simply a font ID (e.g. \"noto-sans\"),
-or as <source ID>:<font ID> (e.g. \"google-fonts:noto-sans\")."
+or as <source ID>:<font ID> (e.g. \"google-fonts:noto-sans\").
+Every font with a tag can be installed with tag:<tag> (e.g. \"google-fonts:tag:monospace\"),
+and fonts with several tags with tag:<tag>+tag:<tag>; these need --confirm."
                     )
                     .action(ArgAction::Append)
                     .required(true),
@@ -286,7 +309,15 @@ or as <source ID>:<font ID> (e.g. \"google-fonts:noto-sans\")."
                     .default_value("flat-directory"),
                 arg!(--"generate-css" "Generate @font-face rules for CSS. Will be ignored without -d.")
                     .alias("css")
-                    .action(ArgAction::SetTrue)
+                    .action(ArgAction::SetTrue),
+                arg!(-k --"keep-going" "Attempt to install every font even if some fail, and print a summary at the end.")
+                    .action(ArgAction::SetTrue),
+                arg!(-c --confirm "Confirms that you want to install every font matching the tag selectors.")
+                    .action(ArgAction::SetTrue),
+                arg!(--subset <subsets> "Only install the given subsets (e.g. latin,cyrillic), for sources that have them.")
+                    .value_delimiter(',')
+                    .action(ArgAction::Append)
+                    .required(false)
             ])
         ,
         runner: Box::new(runner)
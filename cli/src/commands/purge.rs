@@ -2,8 +2,7 @@ use clap::{arg, ArgAction, Command, command, Subcommand, value_parser, ValueEnum
 use fontpm_api::{info, ok, error};
 use tokio::task::JoinSet;
 use crate::commands::{CommandAndRunner, Error};
-use crate::config::FpmConfig;
-use crate::host_impl::FpmHostImpl;
+use fontpm_core::config::FpmConfig;
 use crate::runner;
 
 pub const NAME: &str = "purge";
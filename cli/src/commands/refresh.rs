@@ -1,18 +1,64 @@
-use clap::{arg, ArgAction, ArgMatches, Command};
-use fontpm_api::{debug, error, info, Source, warning};
+use std::path::PathBuf;
+use clap::{arg, ArgAction, ArgGroup, ArgMatches, Command, value_parser};
+use fontpm_api::{debug, error, FpmHost, info, Source, warning};
 use fontpm_api::source::RefreshOutput;
 use fontpm_api::util::{nice_list, plural_s};
 use crate::commands::{Error, CommandAndRunner};
-use crate::host_impl::FpmHostImpl;
+use crate::commands::changes::print_changes;
+use fontpm_core::config::FpmConfig;
+use fontpm_core::host::FpmHostImpl;
 use crate::runner;
 use crate::sources::create_sources;
 
 pub const NAME: &str = "refresh";
 
+/// Replaces the index of a source with one from a file or URL, instead of refreshing it.
+async fn import<'a, 'host>(host: &dyn FpmHost, sources: &'a [Box<dyn Source<'host> + 'host>], source_id: Option<&String>, file: Option<&PathBuf>, url: Option<&String>) -> Result<(&'a dyn Source<'host>, String), Error> {
+    let source = match source_id {
+        Some(id) => sources.iter().find(|v| v.id() == id)
+            .ok_or_else(|| Error::Custom(format!("{} is not an enabled source", id)))?,
+        None if sources.len() == 1 => &sources[0],
+        None => return Err(Error::Custom("more than one source is enabled - specify which one to import the index into with --source".into()))
+    };
+
+    let (index, from) = match (file, url) {
+        (Some(file), _) => (std::fs::read(file)?, file.display().to_string()),
+        (None, Some(url)) => {
+            let client = reqwest::Client::builder()
+                .user_agent(host.user_agent())
+                .build()
+                .map_err(fontpm_api::Error::from)?;
+            let response = client.get(url).send().await
+                .and_then(|v| v.error_for_status())
+                .map_err(fontpm_api::Error::from)?;
+            (response.bytes().await.map_err(fontpm_api::Error::from)?.to_vec(), url.clone())
+        },
+        (None, None) => unreachable!()
+    };
+
+    info!("Importing the index for {} from {}", source.name(), from);
+    source.import_index(&index).await?;
+    Ok((source.as_ref(), format!("Imported the index for {}", source.name())))
+}
+
 runner! { args =>
     let force = args.get_flag("force");
+    let show_changes = args.get_flag("changes");
+    let install_dir = FpmConfig::load()?.font_install_dir();
     let host = FpmHostImpl::create(None)?;
-    let sources = create_sources(Some(&host), None)?;
+    let sources = create_sources(&host, None)?;
+
+    let from_file = args.get_one::<PathBuf>("from-file");
+    let from_url = args.get_one::<String>("from-url");
+    if from_file.is_some() || from_url.is_some() {
+        let result = import(&host, &sources, args.get_one::<String>("source"), from_file, from_url).await?;
+        if show_changes {
+            if let Err(e) = print_changes(result.0, &install_dir).await {
+                error!("[{}] Could not list changes: {}", result.0.name(), e);
+            }
+        }
+        return Ok(Some(result.1))
+    }
 
     {
         let source_display_names: Vec<String> = sources.iter()
@@ -48,10 +94,18 @@ runner! { args =>
     let mut errored = 0;
     let mut downloaded = 0;
     let mut already_up_to_date = 0;
-    for result in results {
+    for (source, result) in sources.iter().zip(results) {
         match result {
             Ok(v) => match v {
-                RefreshOutput::Downloaded => downloaded += 1,
+                RefreshOutput::Downloaded => {
+                    downloaded += 1;
+                    // the refresh itself succeeded, so the other sources are still reported
+                    if show_changes {
+                        if let Err(e) = print_changes(source.as_ref(), &install_dir).await {
+                            error!("[{}] Could not list changes: {}", source.name(), e);
+                        }
+                    }
+                },
                 RefreshOutput::AlreadyUpToDate => already_up_to_date += 1
             },
             Err(_) => errored += 1,
@@ -83,7 +137,25 @@ pub fn command() -> CommandAndRunner {
             .arg(
                 arg!(-f --force "Forces fontpm to pull the data, ignoring any caches.")
                     .action(ArgAction::SetTrue)
-            ),
+            )
+            .arg(
+                arg!(--changes "Lists the fonts that changed in each index that was downloaded.")
+                    .action(ArgAction::SetTrue)
+            )
+            .arg(
+                arg!(--"from-file" <PATH> "Imports the index from a file instead of downloading it.")
+                    .value_parser(value_parser!(PathBuf))
+                    .conflicts_with_all(["force", "from-url"])
+            )
+            .arg(
+                arg!(--"from-url" <URL> "Imports the index from a URL instead of the source's usual location.")
+                    .conflicts_with("force")
+            )
+            .arg(
+                arg!(--source <ID> "The source to import the index into. Only needed if more than one source is enabled.")
+                    .requires("import")
+            )
+            .group(ArgGroup::new("import").args(["from-file", "from-url"])),
         runner: Box::new(runner)
     };
 }
\ No newline at end of file
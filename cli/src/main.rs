@@ -1,10 +1,7 @@
 pub mod commands;
 mod output_impl;
-mod host_impl;
 mod sources;
-mod config;
 mod build_config;
-mod generate;
 
 #[macro_use]
 extern crate lazy_static;
@@ -14,7 +11,7 @@ extern crate async_trait;
 use clap::{arg, ArgAction, Command};
 use clap::parser::ValueSource;
 use fontpm_api::{error, ok, warning};
-use crate::commands::{all_commands, Error};
+use crate::commands::{all_commands, Error, EXIT_FAILURE, EXIT_PARTIAL_FAILURE};
 use crate::output_impl::OutputLevel;
 
 pub const VERSION_STR: &str = env!("CARGO_PKG_VERSION");
@@ -84,13 +81,19 @@ async fn main() {
                 Error::ConfirmationNeeded(message) => {
                     warning!("Confirmation needed: {}", message);
                 }
+                Error::PartialFailure(message) => {
+                    error!("{}", message);
+                    std::process::exit(EXIT_PARTIAL_FAILURE);
+                }
                 e => {
                     error!("{}", e);
+                    std::process::exit(EXIT_FAILURE);
                 }
             }
         }
     } else {
         error!("No such subcommand");
+        std::process::exit(EXIT_FAILURE);
     }
 
 }
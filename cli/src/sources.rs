@@ -1,14 +1,20 @@
-use fontpm_api::{Error, FpmHost, Source};
-use fontpm_source_google_fonts::GoogleFontsSource;
-use crate::config::FpmConfig;
+use fontpm_api::{FpmHost, Source};
+use fontpm_api::registry;
+#[cfg(feature = "plugins")]
+use fontpm_source_process::ProcessSource;
+#[cfg(feature = "wasm")]
+use fontpm_source_wasm::WasmSource;
+use fontpm_api::error;
+use fontpm_core::config::FpmConfig;
 
 /// Create a single source instance.
 ///
 /// # Arguments
 ///
 /// * `source`: The ID of the source to create
+/// * `host`: The host the source will use
 ///
-/// returns: `Some` if the ID refers to an available source, otherwise `None`.
+/// returns: `Some` if the ID refers to a registered source, a source configured with a `type`, or a source plugin, otherwise `None`.
 ///
 /// # Examples
 ///
@@ -16,35 +22,56 @@ use crate::config::FpmConfig;
 /// // create a source using a valid ID (google-fonts)
 /// // note: this example only works if the Google Fonts feature is enabled
 /// let id = String::from("google-fonts");
-/// let source = create_source(&id);
+/// let source = create_source(id, &host);
 /// assert!(source.is_some());
 /// ```
 /// ```
 /// // attempt to create a source using an invalid ID
 /// let id = String::from("some-invalid-id");
-/// let source = create_source(&id);
+/// let source = create_source(id, &host);
 /// assert!(source.is_none());
 /// ```
-pub fn create_source<'host>(source: String, _host: Option<&'host dyn FpmHost>) -> Option<Box<dyn Source<'host> + 'host>> {
-    macro_rules! source {
-        ($source:expr) => {
-            Some({
-                let mut source = $source;
-                if let Some(host) = _host {
-                    source.set_host(host);
-                }
-                Box::new(source)
-            })
+pub fn create_source<'host>(source: String, host: &'host dyn FpmHost) -> Option<Box<dyn Source<'host> + 'host>> {
+    match registry::create_source(source.as_str(), host) {
+        Ok(Some(source)) => return Some(source),
+        Ok(None) => {},
+        Err(e) => {
+            error!("Could not create source {}: {}", source, e);
+            return None
         }
     }
-    match source.as_str() {
-        #[cfg(feature = "google-fonts")]
-        GoogleFontsSource::ID => source!(GoogleFontsSource::new()),
-        _ => None
+    create_plugin_source(source, host)
+}
+
+/// Create a source from a plugin in the plugin directory (or, for process plugins, on `PATH`).
+#[cfg_attr(not(any(feature = "plugins", feature = "wasm")), allow(unused_variables))]
+fn create_plugin_source<'host>(source: String, host: &'host dyn FpmHost) -> Option<Box<dyn Source<'host> + 'host>> {
+    let plugin_dir = FpmConfig::load().ok()?.plugin_dir();
+
+    #[cfg(feature = "plugins")]
+    if let Some(executable) = fontpm_source_process::find(source.as_str(), [&plugin_dir]) {
+        let mut source = ProcessSource::new(executable);
+        source.set_host(host);
+        return Some(Box::new(source))
+    }
+
+    #[cfg(feature = "wasm")]
+    if let Some(path) = fontpm_source_wasm::find(source.as_str(), &plugin_dir) {
+        match WasmSource::load(&source, &path) {
+            Ok(mut source) => {
+                source.set_host(host);
+                return Some(Box::new(source))
+            },
+            Err(e) => {
+                error!("Could not load source {}: {}", source, e);
+            }
+        }
     }
+
+    None
 }
 
-pub fn create_sources<'host>(host: Option<&'host dyn FpmHost>, only: Option<Vec<&String>>) -> fontpm_api::Result<Vec<Box<dyn Source<'host> + 'host>>> {
+pub fn create_sources<'host>(host: &'host dyn FpmHost, only: Option<Vec<&String>>) -> fontpm_api::Result<Vec<Box<dyn Source<'host> + 'host>>> {
     let config = FpmConfig::load()?.clone();
 
     let only = if let Some(v) = only { v.into_iter().collect() } else { Vec::new() };
@@ -61,36 +88,3 @@ pub fn create_sources<'host>(host: Option<&'host dyn FpmHost>, only: Option<Vec<
         .collect()
     );
 }
-
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct FontSpec {
-    pub source: Option<String>,
-    pub font_id: String
-}
-
-impl FontSpec {
-    pub fn parse<S>(v: S) -> Result<FontSpec, Error> where S: ToString {
-        let v = v.to_string();
-        if v.is_empty() {
-            return Err(Error::Generic("Fontspec must not be an empty string".into()))
-        }
-
-        let mut source = None;
-        let mut current = String::new();
-        for c in v.chars() {
-            if c == ':' {
-                if source.is_some() {
-                    return Err(Error::Generic("Character ':' is illegal in font ID".into()))
-                }
-                source = Some(current.clone());
-                current.clear();
-            }
-            current.push(c)
-        }
-
-        Ok(FontSpec {
-            source,
-            font_id: current
-        })
-    }
-}
\ No newline at end of file
@@ -0,0 +1,245 @@
+//! End-to-end tests running the `fontpm` binary against a local stand-in for the Google Fonts index.
+#![cfg(all(target_os = "linux", feature = "google-fonts"))]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use fontpm_testing::{google_fonts_fixture_server, FixtureResponse, FixtureServer};
+use fontpm_testing::google_fonts::{FILES, INDEX};
+use fontpm_testing::layout::{assert_file_contents, assert_installed};
+
+/// Writes a configuration file pointing the google-fonts source at `server`.
+fn configure(home: &Path, server: &FixtureServer) {
+    let config_dir = home.join("config").join("fontpm");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.toml"), format!(r#"
+[fontpm]
+enabled_sources = ["google-fonts"]
+
+[sources.google-fonts]
+index_url = "{}"
+commit_url = "{}"
+file_base_url = "{}"
+"#, server.url_for("/data/google-fonts.json"), server.url_for("/branches/data"), server.url_for("/"))).unwrap();
+}
+
+fn run_fontpm(home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fontpm"))
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .output()
+        .expect("could not run fontpm")
+}
+
+/// Runs fontpm, asserting that it didn't print any errors.
+fn fontpm(home: &Path, args: &[&str]) -> Output {
+    let output = run_fontpm(home, args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("error!"), "fontpm {:?} failed:\n{}", args, stderr);
+    output
+}
+
+#[test]
+fn refresh_and_install_with_css() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    assert!(home.path().join("cache/fontpm/google-fonts/data.json").exists());
+
+    let target = home.path().join("web").join("fonts");
+    fontpm(home.path(), &["install", "test-sans", "test-mono", "-d", target.to_str().unwrap(), "-f", "flat", "--css"]);
+
+    assert_installed(&target, &[
+        "test-mono-regular.ttf",
+        "test-mono.css",
+        "test-sans-700.ttf",
+        "test-sans-OFL.txt",
+        "test-sans-italic.ttf",
+        "test-sans-regular.ttf",
+        "test-sans-variable-italic.ttf",
+        "test-sans-variable.ttf",
+        "test-sans.css"
+    ]);
+    assert_file_contents(target.join("test-sans-700.ttf"), FILES[2]);
+    assert_file_contents(target.join("test-sans-OFL.txt"), FILES[10]);
+
+    let css = fs::read_to_string(target.join("test-sans.css")).unwrap();
+    assert!(css.contains(r#"font-family: "Test Sans";"#));
+    assert!(css.contains(r#"src: url("test-sans-700.ttf");"#));
+    assert!(css.contains("font-weight: 700;"));
+    assert!(css.contains("font-style: italic;"));
+    assert!(css.contains(r#"src: url("test-sans-variable.ttf");"#));
+    assert!(css.contains("font-weight: 1 1000;"));
+}
+
+#[test]
+fn install_subsets_with_css() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("web");
+    fontpm(home.path(), &["install", "test-sans", "--subset", "latin,cyrillic", "-d", target.to_str().unwrap(), "-f", "flat", "--css"]);
+
+    assert_installed(&target, &[
+        "test-sans-700-cyrillic.woff2",
+        "test-sans-700-latin.woff2",
+        "test-sans-OFL.txt",
+        "test-sans-regular-cyrillic.woff2",
+        "test-sans-regular-latin.woff2",
+        "test-sans.css"
+    ]);
+    assert_file_contents(target.join("test-sans-700-cyrillic.woff2"), FILES[9]);
+
+    let css = fs::read_to_string(target.join("test-sans.css")).unwrap();
+    assert!(css.contains(r#"src: url("test-sans-regular-latin.woff2");"#));
+    assert!(css.contains("unicode-range: U+0000-00FF;"));
+    assert!(css.contains("unicode-range: U+0400-045F;"));
+}
+
+#[test]
+fn install_flat_directory() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("fonts");
+    fontpm(home.path(), &["install", "test-mono", "-d", target.to_str().unwrap()]);
+
+    assert_installed(&target, &["test-mono/test-mono-regular.ttf"]);
+}
+
+#[test]
+fn refresh_from_file() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    let index = home.path().join("google-fonts.json");
+    fs::write(&index, INDEX).unwrap();
+    fontpm(home.path(), &["refresh", "--from-file", index.to_str().unwrap()]);
+    assert_eq!(server.requests().len(), 0);
+
+    let target = home.path().join("fonts");
+    fontpm(home.path(), &["install", "test-mono", "-d", target.to_str().unwrap()]);
+    assert_installed(&target, &["test-mono/test-mono-regular.ttf"]);
+
+    fs::write(&index, "not an index").unwrap();
+    let output = run_fontpm(home.path(), &["refresh", "--from-file", index.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    fontpm(home.path(), &["install", "test-mono", "-d", target.to_str().unwrap()]);
+
+    fontpm(home.path(), &["refresh", "--from-url", &server.url_for("/data/google-fonts.json"), "--source", "google-fonts"]);
+    assert_eq!(server.request_count("/data/google-fonts.json"), 1);
+    assert_eq!(server.request_count("/branches/data"), 0);
+}
+
+#[test]
+fn refresh_lists_changes() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    fontpm(home.path(), &["install", "test-mono"]);
+    let output = fontpm(home.path(), &["changes"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("There is no previous index"));
+
+    let mut index = INDEX.replace("\"version\": 1", "\"version\": 2");
+    index = index.replacen("\"test-sans\": {", "\"test-serif\": {\"id\": \"test-serif\", \"display_name\": \"Test Serif\", \"version\": 1, \"tags\": [], \"lastModified\": 0, \"files\": {}, \"variants\": []},\n    \"test-sans\": {", 1);
+    server.route("/data/google-fonts.json", FixtureResponse::ok(index));
+    let output = fontpm(home.path(), &["refresh", "--force", "--changes"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<Vec<&str>> = stdout.lines()
+        .skip_while(|v| !v.contains("Changes since the previous index (1 installed)"))
+        .skip(2)
+        .take(2)
+        .map(|v| v.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows, vec![
+        vec!["added", "test-serif", "Test", "Serif", "(version", "1)"],
+        vec!["updated", "test-mono", "yes", "version", "1", "->", "2"]
+    ]);
+}
+
+#[test]
+fn refresh_succeeds_when_changes_cannot_be_listed() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    // becomes the previous index, which can't be compared with
+    fs::write(home.path().join("cache/fontpm/google-fonts/data.json"), "not an index").unwrap();
+    let output = run_fontpm(home.path(), &["refresh", "--force", "--changes"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not list changes"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 source refreshed"));
+}
+
+#[test]
+fn install_by_tag_needs_confirmation() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("fonts");
+    let output = fontpm(home.path(), &["install", "google-fonts:tag:monospace", "-d", target.to_str().unwrap()]);
+    let printed = String::from_utf8_lossy(&output.stdout).to_string() + &String::from_utf8_lossy(&output.stderr);
+    assert!(printed.contains("google-fonts:test-mono - Test Mono"), "{}", printed);
+    assert!(printed.contains("Install 1 font (unknown size)?"), "{}", printed);
+    assert!(!target.exists());
+
+    fontpm(home.path(), &["install", "tag:monospace", "--confirm", "-d", target.to_str().unwrap()]);
+    assert_installed(&target, &["test-mono/test-mono-regular.ttf"]);
+
+    let output = run_fontpm(home.path(), &["install", "tag:monospace+tag:sans-serif", "-d", target.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn keep_going_on_partial_failure() {
+    let server = google_fonts_fixture_server();
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path(), &server);
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("fonts");
+    let output = run_fontpm(home.path(), &["install", "test-mono", "no-such-font", "test-sans", "-d", target.to_str().unwrap(), "--keep-going"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_installed(&target, &[
+        "test-mono/test-mono-regular.ttf",
+        "test-sans/OFL.txt",
+        "test-sans/test-sans-700.ttf",
+        "test-sans/test-sans-italic.ttf",
+        "test-sans/test-sans-regular.ttf",
+        "test-sans/test-sans-variable-italic.ttf",
+        "test-sans/test-sans-variable.ttf"
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let summary: Vec<Vec<&str>> = stdout.lines()
+        .skip_while(|v| !v.contains("Summary:"))
+        .skip(2)
+        .map(|v| v.split_whitespace().take(2).collect())
+        .collect();
+    assert_eq!(summary, vec![
+        vec!["installed", "test-mono"],
+        vec!["installed", "test-sans"],
+        vec!["failed", "no-such-font"]
+    ]);
+
+    let output = run_fontpm(home.path(), &["install", "no-such-font", "-d", target.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+}
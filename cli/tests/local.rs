@@ -0,0 +1,63 @@
+//! End-to-end tests running the `fontpm` binary with the local source pointed at a directory of fonts.
+#![cfg(all(target_os = "linux", feature = "local"))]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use fontpm_testing::FixtureFont;
+use fontpm_testing::layout::assert_installed;
+
+/// Writes a few styles of Proxima Nova into `fonts`, and a configuration file that only uses the local source with it.
+fn configure(home: &Path) {
+    let fonts = home.join("nas").join("fonts");
+    fs::create_dir_all(fonts.join("italic")).unwrap();
+    fs::write(fonts.join("ProximaNova-Regular.otf"), FixtureFont::new("Proxima Nova").build()).unwrap();
+    fs::write(fonts.join("ProximaNova-Bold.otf"), FixtureFont::new("Proxima Nova").with_weight(700).build()).unwrap();
+    fs::write(fonts.join("italic").join("ProximaNova-Italic.otf"), FixtureFont::new("Proxima Nova").italic().build()).unwrap();
+
+    let config_dir = home.join("config").join("fontpm");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.toml"), format!(r#"
+[fontpm]
+enabled_sources = ["local"]
+
+[sources.local]
+directories = ["{}"]
+"#, fonts.display())).unwrap();
+}
+
+/// Runs fontpm, asserting that it didn't print any errors.
+fn fontpm(home: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_fontpm"))
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .output()
+        .expect("could not run fontpm");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("error!"), "fontpm {:?} failed:\n{}", args, stderr);
+    output
+}
+
+#[test]
+fn install_from_directory_with_css() {
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path());
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("web").join("fonts");
+    fontpm(home.path(), &["install", "local:proxima-nova", "-d", target.to_str().unwrap(), "-f", "flat", "--css"]);
+
+    assert_installed(&target, &[
+        "proxima-nova-700.otf",
+        "proxima-nova-italic.otf",
+        "proxima-nova-regular.otf",
+        "proxima-nova.css"
+    ]);
+    assert_eq!(fs::read(target.join("proxima-nova-700.otf")).unwrap(), FixtureFont::new("Proxima Nova").with_weight(700).build());
+    let css = fs::read_to_string(target.join("proxima-nova.css")).unwrap();
+    assert!(css.contains(r#"font-family: "Proxima Nova";"#));
+    assert!(css.contains(r#"src: url("proxima-nova-700.otf");"#));
+}
@@ -0,0 +1,68 @@
+//! End-to-end tests running the `fontpm` binary with a registry backed by an index on disk.
+#![cfg(all(target_os = "linux", feature = "registry"))]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use fontpm_testing::FixtureFont;
+use fontpm_testing::layout::assert_installed;
+
+/// Writes a registry with two styles of ACME Sans into `registry`, and a configuration file that only uses it as `acme`.
+fn configure(home: &Path) {
+    let registry = home.join("registry");
+    fs::create_dir_all(registry.join("acme-sans")).unwrap();
+    fs::write(registry.join("acme-sans").join("AcmeSans-Regular.otf"), FixtureFont::new("ACME Sans").build()).unwrap();
+    fs::write(registry.join("acme-sans").join("AcmeSans-Bold.otf"), FixtureFont::new("ACME Sans").with_weight(700).build()).unwrap();
+    fs::write(registry.join("index.toml"), r#"
+[families.acme-sans]
+name = "ACME Sans"
+version = "1.0"
+tags = ["brand"]
+
+[families.acme-sans.variants]
+regular = { path = "acme-sans/AcmeSans-Regular.otf" }
+700 = { path = "acme-sans/AcmeSans-Bold.otf" }
+"#).unwrap();
+
+    let config_dir = home.join("config").join("fontpm");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.toml"), format!(r#"
+[fontpm]
+enabled_sources = ["acme"]
+
+[sources.acme]
+type = "registry"
+index = "{}"
+"#, registry.join("index.toml").display())).unwrap();
+}
+
+/// Runs fontpm, asserting that it didn't print any errors.
+fn fontpm(home: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_fontpm"))
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .output()
+        .expect("could not run fontpm");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("error!"), "fontpm {:?} failed:\n{}", args, stderr);
+    output
+}
+
+#[test]
+fn install_from_registry() {
+    let home = tempfile::tempdir().unwrap();
+    configure(home.path());
+
+    fontpm(home.path(), &["refresh"]);
+    let target = home.path().join("web").join("fonts");
+    fontpm(home.path(), &["install", "acme:tag:brand", "-d", target.to_str().unwrap(), "-f", "flat", "-c"]);
+
+    assert_installed(&target, &[
+        "acme-sans-700.otf",
+        "acme-sans-regular.otf"
+    ]);
+    assert_eq!(fs::read(target.join("acme-sans-700.otf")).unwrap(), FixtureFont::new("ACME Sans").with_weight(700).build());
+}
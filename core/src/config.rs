@@ -4,8 +4,9 @@ use std::io::{Read};
 use serde::{Deserialize, Serialize};
 use toml::Value;
 use fontpm_api::{Result as FResult, Error};
+use fontpm_api::registry;
+use fontpm_api::host::DEFAULT_MAX_CONCURRENT_DOWNLOADS;
 use fontpm_api::util::create_parent;
-use crate::build_config;
 
 #[derive(Default, Deserialize, Serialize, Clone, PartialEq)]
 pub struct EntireConfig {
@@ -92,7 +93,9 @@ impl EntireConfig {
 pub struct FpmConfig {
     pub enabled_sources: Vec<String>,
     pub cache_dir: Option<PathBuf>,
-    pub font_install_dir: Option<PathBuf>
+    pub font_install_dir: Option<PathBuf>,
+    pub plugin_dir: Option<PathBuf>,
+    pub max_concurrent_downloads: Option<usize>
 }
 
 impl FpmConfig {
@@ -116,14 +119,28 @@ impl FpmConfig {
             cache_dir
         })
     }
+
+    pub fn max_concurrent_downloads(&self) -> usize {
+        self.max_concurrent_downloads.unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS).max(1)
+    }
+
+    pub fn plugin_dir(&self) -> PathBuf {
+        return self.plugin_dir.clone().unwrap_or_else(|| {
+            let mut plugin_dir = EntireConfig::config_dir();
+            plugin_dir.push("plugins");
+            plugin_dir
+        })
+    }
 }
 
 impl Default for FpmConfig {
     fn default() -> Self {
         return FpmConfig {
-            enabled_sources: build_config::sources(),
+            enabled_sources: registry::default_sources(),
             cache_dir: None,
-            font_install_dir: None
+            font_install_dir: None,
+            plugin_dir: None,
+            max_concurrent_downloads: None
         }
     }
 }
\ No newline at end of file
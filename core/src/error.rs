@@ -0,0 +1,28 @@
+use fontpm_api::util::nice_list;
+use crate::generate::GenerateError;
+use crate::install::ResolveFailure;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("no sources to install from")]
+    NoSources,
+    #[error("could not resolve {}", nice_list(.0.iter().map(|v| &v.spec), "and"))]
+    Resolve(Vec<ResolveFailure>),
+    #[error("could not download font {font} from {source_name}: {error}")]
+    Download {
+        font: String,
+        source_name: String,
+        error: fontpm_api::Error
+    },
+    #[error("{0}")]
+    API(#[from] fontpm_api::Error),
+    #[error("generation failed: {0}")]
+    Generate(#[from] GenerateError)
+}
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::API(fontpm_api::Error::IO(value))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use path_calculate::Calculate;
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription, FontSubset};
+
+#[derive(Clone)]
+struct FontFace {
+    desc: FontDescription,
+    spec: DefinedFontVariantSpec,
+    subset: Option<FontSubset>,
+    path: PathBuf
+}
+
+#[derive(Clone)]
+pub struct Generate {
+    target_file: PathBuf,
+    fonts: Vec<FontFace>
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GenerateError {
+    #[error("relative_path error: {0}")]
+    PathError(std::io::Error),
+    #[error("could not write stylesheet: {0}")]
+    Write(std::io::Error)
+}
+
+impl Generate {
+    pub fn from_font<P: AsRef<Path>>(target_file: impl AsRef<Path>, desc: impl AsRef<FontDescription>, files: impl IntoIterator<Item = (DefinedFontVariantSpec, P)>) -> Self {
+        let desc = desc.as_ref();
+        Self {
+            target_file: target_file.as_ref().to_path_buf(),
+            fonts: files.into_iter()
+                .map(|(spec, path)| FontFace { desc: desc.clone(), spec, subset: None, path: path.as_ref().to_path_buf() })
+                .collect()
+        }
+    }
+    /// Like [`Generate::from_font`], but with a file per subset of each variant, which browsers only load for the characters in the subset.
+    pub fn from_subsets<P: AsRef<Path>>(target_file: impl AsRef<Path>, desc: impl AsRef<FontDescription>, files: impl IntoIterator<Item = (DefinedFontVariantSpec, FontSubset, P)>) -> Self {
+        let desc = desc.as_ref();
+        Self {
+            target_file: target_file.as_ref().to_path_buf(),
+            fonts: files.into_iter()
+                .map(|(spec, subset, path)| FontFace { desc: desc.clone(), spec, subset: Some(subset), path: path.as_ref().to_path_buf() })
+                .collect()
+        }
+    }
+    fn sorted_entries(&self) -> Vec<&FontFace> {
+        let mut vec: Vec<&FontFace> = self.fonts.iter().collect();
+        vec.sort_by(|a, b| {
+            a.desc.name.cmp(&b.desc.name)
+                .then_with(|| a.spec.cmp(&b.spec))
+                .then_with(|| a.subset.as_ref().map(|v| &v.name).cmp(&b.subset.as_ref().map(|v| &v.name)))
+        });
+        vec
+    }
+    pub fn generate_css(&self) -> Result<String, GenerateError> {
+        let mut stylesheet = String::from("/* Generated by FontPM -- If there is an error, please report it at https://github.com/tecc/fontpm */\n");
+
+        let target_parent = self.target_file.parent().unwrap();
+        for FontFace { desc, spec, subset, path } in self.sorted_entries() {
+            let relation = path.related_to(&target_parent).map_err(GenerateError::PathError)?.to_path_buf();
+            let subset_name = subset.as_ref().map_or(String::new(), |v| format!(" ({})", v.name));
+            let unicode_range = subset.as_ref()
+                .and_then(|v| v.unicode_range.as_ref())
+                .map_or(String::new(), |v| format!("\n    unicode-range: {};", v));
+
+            let rule = format!(r#"
+@font-face {{
+    /* {} */
+    font-family: "{}";
+    src: url("{}");
+    font-style: {};{}{}
+}}
+            "#, format_args!("{} v{} - {} {}{}", desc.name, desc.version, spec.weight, spec.style, subset_name), desc.name, relation.display(), match spec.style {
+                DefinedFontStyle::Regular => "normal",
+                DefinedFontStyle::Italic => "italic"
+            }, match spec.weight {
+                DefinedFontWeight::Fixed(w) => format!("\n    font-weight: {};", w.clone()),
+                // NOTE: browsers clamp this to the weights the font actually supports
+                DefinedFontWeight::Variable => "\n    font-weight: 1 1000;".to_string()
+            }, unicode_range);
+            stylesheet.push_str(rule.as_str());
+        }
+
+        Ok(stylesheet)
+    }
+}
\ No newline at end of file
@@ -1,24 +1,35 @@
 use std::path::PathBuf;
 use semver::Version;
 use fontpm_api::FpmHost;
-use crate::config::{EntireConfig};
+use crate::config::EntireConfig;
 
 #[derive(Clone)]
 pub struct FpmHostImpl {
     cache_dir: PathBuf,
     font_install_dir: PathBuf,
+    max_concurrent_downloads: usize,
     config: EntireConfig,
 }
 
 impl FpmHostImpl {
+    /// Creates a host using the configuration file (see [`EntireConfig::load`]).
     pub fn create(font_install_dir: Option<PathBuf>) -> fontpm_api::Result<FpmHostImpl> {
         let cfg = EntireConfig::load()?;
+        Ok(Self::from_config(cfg, font_install_dir))
+    }
+    /// Creates a host using `cfg` instead of the configuration file.
+    pub fn from_config(cfg: EntireConfig, font_install_dir: Option<PathBuf>) -> FpmHostImpl {
         let fontpm = cfg.fontpm();
-        Ok(FpmHostImpl {
+        FpmHostImpl {
             cache_dir: fontpm.cache_dir(),
             font_install_dir: font_install_dir.unwrap_or(fontpm.font_install_dir()),
+            max_concurrent_downloads: fontpm.max_concurrent_downloads(),
             config: cfg,
-        })
+        }
+    }
+
+    pub fn entire_config(&self) -> &EntireConfig {
+        &self.config
     }
 }
 
@@ -42,10 +53,14 @@ impl FpmHost for FpmHostImpl {
     }
 
     fn version(&self) -> Version {
-        crate::VERSION.clone()
+        Version::parse(crate::VERSION_STR).unwrap()
     }
 
     fn user_agent(&self) -> String {
         format!("FontPM/{}", self.version())
     }
+
+    fn max_concurrent_downloads(&self) -> usize {
+        self.max_concurrent_downloads
+    }
 }
\ No newline at end of file
@@ -0,0 +1,659 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::{copy, create_dir_all};
+use std::path::{Path, PathBuf};
+use futures::StreamExt;
+use multimap::MultiMap;
+use path_clean::PathClean;
+use fontpm_api::{FpmHost, Source};
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontDescription, FontInstallSpec, FontSubset};
+use fontpm_api::source::SourceDescription;
+use fontpm_api::util::nice_list;
+use crate::error::{Error, Result};
+use crate::generate::{Generate, GenerateError};
+use crate::layout::Layout;
+use crate::spec::{FontSelector, FontSpec};
+
+/// Progress reported to the observer set with [`Installer::observe`].
+#[derive(Debug)]
+pub enum InstallEvent<'a> {
+    /// Fonts are being resolved from a source.
+    Resolving { source: &'a SourceDescription },
+    /// A font is being downloaded and installed.
+    Installing { font: &'a ResolvedFont },
+    /// A downloaded file is being copied to the target directory.
+    Copying { from: &'a Path, to: &'a Path },
+    /// A font will not be installed, see [`SkipReason`].
+    Skipped { font: &'a SkippedFont },
+    /// A font could not be installed. Only reported if [`Installer::keep_going`] is enabled.
+    Failed { failure: &'a FontFailure }
+}
+
+type Observer<'host> = Box<dyn Fn(InstallEvent) + Send + Sync + 'host>;
+
+/// A font that has been resolved, but not yet installed.
+#[derive(Clone, Debug)]
+pub struct ResolvedFont {
+    /// The spec the font was requested with.
+    pub spec: FontSpec,
+    /// The source the font was resolved from.
+    pub source: SourceDescription,
+    /// The exact variants that will be installed.
+    pub install_spec: DefinedFontInstallSpec,
+    pub description: FontDescription
+}
+
+/// A font that could not be resolved from any of the sources it was looked up in.
+#[derive(Debug)]
+pub struct ResolveFailure {
+    pub spec: FontSpec,
+    /// The names of the sources the font was looked up in.
+    pub sources: Vec<String>,
+    /// The error from the last source the font was looked up in.
+    pub error: fontpm_api::Error
+}
+
+/// A font in the group of a [`FontSelector`], found by [`Installer::expand`].
+#[derive(Clone, Debug)]
+pub struct SelectedFont {
+    /// The spec to install the font with, which names the source it was found in.
+    pub spec: FontSpec,
+    pub source: SourceDescription,
+    pub font: FontDescription,
+    /// The total size of the font's files in bytes, if the source knows it.
+    pub size: Option<u64>
+}
+
+#[derive(Clone, Debug)]
+pub struct InstalledFont {
+    pub font: ResolvedFont,
+    /// The installed file of each variant. Empty if subsets were installed instead.
+    pub files: BTreeMap<DefinedFontVariantSpec, PathBuf>,
+    /// The installed file of each subset of each variant, keyed by subset name, if subsets were requested and the source has them.
+    pub subset_files: BTreeMap<DefinedFontVariantSpec, BTreeMap<String, PathBuf>>,
+    /// The installed license documents, keyed by file name.
+    pub licenses: BTreeMap<String, PathBuf>,
+    /// The generated stylesheet, if [`Installer::generate_css`] was enabled.
+    pub stylesheet: Option<PathBuf>
+}
+
+impl InstalledFont {
+    /// Every installed font file, of both full fonts and subsets.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.values().chain(self.subset_files.values().flat_map(BTreeMap::values))
+    }
+}
+
+/// Why a font was not installed, even though nothing went wrong.
+#[derive(Clone, Debug)]
+pub enum SkipReason {
+    /// Another spec resolved to the same font from the same source, and was installed instead.
+    Duplicate(FontSpec),
+    /// The source has no files for the font.
+    NoVariants
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Duplicate(other) => write!(f, "same font as {}", other),
+            SkipReason::NoVariants => f.write_str("no files available")
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SkippedFont {
+    pub font: ResolvedFont,
+    pub reason: SkipReason
+}
+
+/// A variant of a font that could not be downloaded, or copied to the target directory.
+#[derive(thiserror::Error, Debug)]
+#[error("{} {}: {}", .variant.weight, .variant.style, .error)]
+pub struct VariantFailure {
+    pub variant: DefinedFontVariantSpec,
+    pub error: fontpm_api::Error
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FontError {
+    #[error("could not resolve: {error}")]
+    Resolve {
+        /// The names of the sources the font was looked up in.
+        sources: Vec<String>,
+        error: fontpm_api::Error
+    },
+    #[error("could not download from {source_name}: {error}")]
+    Download {
+        source_name: String,
+        error: fontpm_api::Error
+    },
+    #[error("could not install {}", nice_list(.failed, "and"))]
+    Variants {
+        /// The variants that were installed anyway.
+        installed: BTreeMap<DefinedFontVariantSpec, PathBuf>,
+        failed: Vec<VariantFailure>
+    },
+    #[error("could not install the license from {source_name}: {error}")]
+    License {
+        source_name: String,
+        error: fontpm_api::Error
+    },
+    #[error("could not generate stylesheet: {0}")]
+    Stylesheet(GenerateError)
+}
+
+/// A font that could not be installed.
+#[derive(Debug)]
+pub struct FontFailure {
+    pub spec: FontSpec,
+    /// The font the spec resolved to, if it could be resolved.
+    pub font: Option<ResolvedFont>,
+    pub error: FontError
+}
+
+impl FontFailure {
+    fn new(font: ResolvedFont, error: FontError) -> Self {
+        FontFailure {
+            spec: font.spec.clone(),
+            font: Some(font),
+            error
+        }
+    }
+}
+
+impl From<ResolveFailure> for FontFailure {
+    fn from(value: ResolveFailure) -> Self {
+        FontFailure {
+            spec: value.spec,
+            font: None,
+            error: FontError::Resolve {
+                sources: value.sources,
+                error: value.error
+            }
+        }
+    }
+}
+
+impl From<FontFailure> for Error {
+    fn from(value: FontFailure) -> Self {
+        match value.error {
+            FontError::Resolve { sources, error } => Error::Resolve(vec![ResolveFailure { spec: value.spec, sources, error }]),
+            FontError::Download { source_name, error } | FontError::License { source_name, error } => Error::Download {
+                font: value.font.map_or(value.spec.font_id, |v| v.description.name),
+                source_name,
+                error
+            },
+            FontError::Variants { mut failed, .. } => failed.remove(0).error.into(),
+            FontError::Stylesheet(e) => e.into()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InstallReport {
+    /// The absolute path of the directory the fonts were installed to.
+    pub target: PathBuf,
+    /// The installed fonts, in the order they were added.
+    pub fonts: Vec<InstalledFont>,
+    pub skipped: Vec<SkippedFont>,
+    /// The fonts that could not be installed. Always empty unless [`Installer::keep_going`] is enabled.
+    pub failed: Vec<FontFailure>
+}
+
+impl InstallReport {
+    /// Whether every font was either installed or skipped.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The files of a font in the cache, before they're copied to the target directory.
+#[derive(Default)]
+struct Downloaded {
+    files: HashMap<DefinedFontVariantSpec, PathBuf>,
+    subset_files: HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>>,
+    licenses: HashMap<String, PathBuf>,
+    /// The variants that could not be downloaded, if others could.
+    failed: Vec<VariantFailure>
+}
+
+/// Resolves, downloads and installs fonts.
+///
+/// Fonts with a source in their spec are only looked up in that source;
+/// other fonts are looked up in every source, in order, and the first source that has the font wins.
+pub struct Installer<'host> {
+    host: &'host dyn FpmHost,
+    sources: Vec<Box<dyn Source<'host> + 'host>>,
+    specs: Vec<FontSpec>,
+    target: Option<PathBuf>,
+    layout: Layout,
+    generate_css: bool,
+    keep_going: bool,
+    subsets: Vec<String>,
+    observer: Option<Observer<'host>>
+}
+
+impl<'host> Installer<'host> {
+    pub fn new(host: &'host dyn FpmHost) -> Self {
+        Installer {
+            host,
+            sources: Vec::new(),
+            specs: Vec::new(),
+            target: None,
+            layout: Layout::default(),
+            generate_css: false,
+            keep_going: false,
+            subsets: Vec::new(),
+            observer: None
+        }
+    }
+
+    /// Adds a source to install fonts from.
+    pub fn source(mut self, source: Box<dyn Source<'host> + 'host>) -> Self {
+        self.sources.push(source);
+        self
+    }
+    /// Adds several sources to install fonts from.
+    pub fn sources(mut self, sources: impl IntoIterator<Item = Box<dyn Source<'host> + 'host>>) -> Self {
+        self.sources.extend(sources);
+        self
+    }
+    /// Adds a font to install. Adding the same spec twice has no effect.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, spec: FontSpec) -> Self {
+        if !self.specs.contains(&spec) {
+            self.specs.push(spec);
+        }
+        self
+    }
+    /// Sets the directory to install to. Defaults to the host's font install directory.
+    /// Relative paths are resolved against the current directory.
+    pub fn target(mut self, target: impl Into<PathBuf>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+    /// Whether to generate a stylesheet with `@font-face` rules next to every installed font.
+    pub fn generate_css(mut self, generate_css: bool) -> Self {
+        self.generate_css = generate_css;
+        self
+    }
+    /// Whether to attempt every font even if some fail, instead of stopping at the first failure.
+    /// Failures are then listed in [`InstallReport::failed`].
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+    /// Sets the subsets (e.g. `latin`) to install instead of the full fonts.
+    /// Fonts from sources without subsets are still installed in full.
+    pub fn subsets<S: ToString>(mut self, subsets: impl IntoIterator<Item = S>) -> Self {
+        self.subsets = subsets.into_iter().map(|v| v.to_string()).collect();
+        self
+    }
+    /// Sets a function to call whenever progress is made.
+    pub fn observe(mut self, observer: impl Fn(InstallEvent) + Send + Sync + 'host) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    pub fn specs(&self) -> &[FontSpec] {
+        &self.specs
+    }
+
+    /// Finds every font in the group of `selector`, which can then be added with [`Installer::add`].
+    ///
+    /// Selectors without a source are looked up in every source, in order,
+    /// and fonts that more than one source has are only selected from the first.
+    pub async fn expand(&self, selector: &FontSelector) -> Result<Vec<SelectedFont>> {
+        if self.sources.is_empty() {
+            return Err(Error::NoSources)
+        }
+        let target_sources: Vec<_> = match &selector.source {
+            Some(id) => self.sources.iter().filter(|v| v.id() == id).collect(),
+            None => self.sources.iter().collect()
+        };
+        if let (Some(id), true) = (&selector.source, target_sources.is_empty()) {
+            return Err(fontpm_api::Error::Generic(format!("no source with the ID {} is available", id)).into())
+        }
+
+        let mut selected: Vec<SelectedFont> = Vec::new();
+        for source in target_sources {
+            let description = source.description();
+            for found in source.search_by_tags(&selector.tags).await? {
+                if selected.iter().any(|v| v.font.id == found.font.id) {
+                    continue
+                }
+                selected.push(SelectedFont {
+                    spec: FontSpec::new(Some(source.id()), &found.font.id),
+                    source: description.clone(),
+                    font: found.font,
+                    size: found.size
+                });
+            }
+        }
+        Ok(selected)
+    }
+
+    fn emit(&self, event: InstallEvent) {
+        if let Some(observer) = &self.observer {
+            observer(event)
+        }
+    }
+
+    fn target_dir(&self) -> Result<PathBuf> {
+        let mut dir = self.target.clone()
+            .unwrap_or_else(|| self.host.font_install_dir())
+            .clean();
+        if dir.is_relative() {
+            dir = std::env::current_dir()?.join(dir);
+        }
+        Ok(dir)
+    }
+
+    async fn resolve_all(&self) -> Result<(Vec<ResolvedFont>, Vec<ResolveFailure>)> {
+        if self.sources.is_empty() {
+            return Err(Error::NoSources)
+        }
+
+        // NOTE(tecc): `None` means "any source"
+        let mut specs_by_source = MultiMap::new();
+        for spec in &self.specs {
+            specs_by_source.insert(spec.source.clone(), spec);
+        }
+
+        let mut results = HashMap::new();
+        for (source_id, specs) in specs_by_source.iter_all() {
+            let target_sources: Vec<_> = match source_id {
+                Some(id) => self.sources.iter().filter(|v| v.id() == id).collect(),
+                None => self.sources.iter().collect()
+            };
+            let source_names: Vec<String> = target_sources.iter().map(|v| v.name().to_string()).collect();
+
+            let mut group: HashMap<&FontSpec, fontpm_api::Result<ResolvedFont>> = HashMap::new();
+            for source in target_sources {
+                let description = source.description();
+                self.emit(InstallEvent::Resolving { source: &description });
+
+                let unresolved = specs.iter()
+                    .filter(|spec| !matches!(group.get(*spec), Some(Ok(_))))
+                    .map(|spec| {
+                        let description = description.clone();
+                        async move {
+                            let resolved = source.resolve_font(&FontInstallSpec::new_all_styles(&spec.font_id).with_subsets(&self.subsets)).await
+                                .map(|(install_spec, font)| ResolvedFont {
+                                    spec: (*spec).clone(),
+                                    source: description,
+                                    install_spec,
+                                    description: font
+                                });
+                            (*spec, resolved)
+                        }
+                    });
+                for (spec, resolved) in futures::future::join_all(unresolved).await {
+                    group.insert(spec, resolved);
+                }
+            }
+
+            for spec in specs {
+                let result = group.remove(*spec).unwrap_or_else(|| Err(fontpm_api::Error::Generic(
+                    format!("no source with the ID {} is available", source_id.as_deref().unwrap_or_default())
+                )));
+                results.insert(*spec, result.map_err(|error| ResolveFailure {
+                    spec: (*spec).clone(),
+                    sources: source_names.clone(),
+                    error
+                }));
+            }
+        }
+
+        let mut resolved = Vec::new();
+        let mut failures = Vec::new();
+        for spec in &self.specs {
+            match results.remove(spec).expect("every spec should have been resolved") {
+                Ok(v) => resolved.push(v),
+                Err(e) => failures.push(e)
+            }
+        }
+        Ok((resolved, failures))
+    }
+
+    /// Resolves every added font, without downloading anything.
+    ///
+    /// returns: The resolved fonts in the order they were added,
+    /// or [`Error::Resolve`] with every font that could not be resolved.
+    pub async fn resolve(&self) -> Result<Vec<ResolvedFont>> {
+        let (resolved, failures) = self.resolve_all().await?;
+        if failures.is_empty() {
+            Ok(resolved)
+        } else {
+            Err(Error::Resolve(failures))
+        }
+    }
+
+    /// Resolves and installs every added font.
+    ///
+    /// Unless [`Installer::keep_going`] is enabled, nothing is downloaded unless every font could be resolved,
+    /// and installing stops at the first font that fails.
+    /// Otherwise, every font is attempted, and the failures are listed in the report.
+    pub async fn run(self) -> Result<InstallReport> {
+        let (resolved, resolve_failures) = self.resolve_all().await?;
+        if !self.keep_going && !resolve_failures.is_empty() {
+            return Err(Error::Resolve(resolve_failures))
+        }
+        let mut failed: Vec<FontFailure> = resolve_failures.into_iter().map(FontFailure::from).collect();
+        for failure in &failed {
+            self.emit(InstallEvent::Failed { failure });
+        }
+
+        let target = self.target_dir()?;
+        create_dir_all(&target)?;
+
+        let mut skipped = Vec::new();
+        let mut to_install = Vec::new();
+        let mut installed_by: HashMap<(String, String), FontSpec> = HashMap::new();
+        for font in resolved {
+            let key = (font.source.id.clone(), font.install_spec.id.clone());
+            let reason = if let Some(other) = installed_by.get(&key) {
+                Some(SkipReason::Duplicate(other.clone()))
+            } else if font.install_spec.styles.is_empty() {
+                Some(SkipReason::NoVariants)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                let font = SkippedFont { font, reason };
+                self.emit(InstallEvent::Skipped { font: &font });
+                skipped.push(font);
+                continue
+            }
+            installed_by.insert(key, font.spec.clone());
+            to_install.push(font);
+        }
+
+        // fonts are downloaded at the same time (as far as the host allows),
+        // but copied one at a time in the order they were added, so that the results don't depend on which download finishes first
+        let mut fonts = Vec::new();
+        let installer = &self;
+        let mut downloads = futures::stream::iter(to_install)
+            .map(|font| async move {
+                let downloaded = installer.download(&font).await;
+                (font, downloaded)
+            })
+            .buffered(self.host.max_concurrent_downloads().max(1));
+        while let Some((font, downloaded)) = downloads.next().await {
+            let installed = match downloaded {
+                Ok(downloaded) => self.install(&target, font, downloaded),
+                Err(error) => Err(FontFailure::new(font, error))
+            };
+            match installed {
+                Ok(installed) => fonts.push(installed),
+                Err(failure) => {
+                    if !self.keep_going {
+                        return Err(failure.into())
+                    }
+                    self.emit(InstallEvent::Failed { failure: &failure });
+                    failed.push(failure);
+                }
+            }
+        }
+
+        Ok(InstallReport {
+            target,
+            fonts,
+            skipped,
+            failed
+        })
+    }
+
+    /// Downloads every file of `font` to the cache.
+    async fn download(&self, font: &ResolvedFont) -> std::result::Result<Downloaded, FontError> {
+        self.emit(InstallEvent::Installing { font });
+        let source = self.sources.iter()
+            .find(|v| v.id() == font.source.id)
+            .expect("logic error");
+        let source_name = || font.source.name.clone();
+
+        let cache_dir = self.host.cache_dir_for(source.id());
+        let mut downloaded = match self.download_variants(source.as_ref(), &font.install_spec, &cache_dir).await {
+            Ok(v) => v,
+            Err(error) if !self.keep_going || font.install_spec.styles.len() < 2 => return Err(FontError::Download { source_name: source_name(), error }),
+            // with keep_going, every variant is downloaded on its own, so that the ones that work can still be installed
+            Err(error) => {
+                let mut downloaded = Downloaded::default();
+                for variant in &font.install_spec.styles {
+                    let spec = DefinedFontInstallSpec { styles: vec![*variant], ..font.install_spec.clone() };
+                    match self.download_variants(source.as_ref(), &spec, &cache_dir).await {
+                        Ok(v) => {
+                            downloaded.files.extend(v.files);
+                            downloaded.subset_files.extend(v.subset_files);
+                        },
+                        Err(error) => downloaded.failed.push(VariantFailure { variant: *variant, error })
+                    }
+                }
+                if downloaded.failed.len() == font.install_spec.styles.len() {
+                    return Err(FontError::Download { source_name: source_name(), error })
+                }
+                downloaded
+            }
+        };
+        downloaded.licenses = source.download_licenses(&font.install_spec, &cache_dir).await
+            .map_err(|error| FontError::License { source_name: source_name(), error })?;
+        Ok(downloaded)
+    }
+
+    /// Downloads the font files of `spec`, or its subset files if it has subsets.
+    async fn download_variants(&self, source: &dyn Source<'host>, spec: &DefinedFontInstallSpec, cache_dir: &PathBuf) -> fontpm_api::Result<Downloaded> {
+        let mut downloaded = Downloaded::default();
+        if spec.subsets.is_empty() {
+            downloaded.files = source.download_font(spec, cache_dir).await?;
+        } else {
+            downloaded.subset_files = source.download_subsets(spec, cache_dir).await?;
+        }
+        Ok(downloaded)
+    }
+
+    /// Copies the `downloaded` files of `font` to `target`, and generates its stylesheet.
+    #[allow(clippy::result_large_err)]
+    fn install(&self, target: &Path, font: ResolvedFont, downloaded: Downloaded) -> std::result::Result<InstalledFont, FontFailure> {
+        let mut files = BTreeMap::new();
+        let mut subset_files = BTreeMap::new();
+        let mut failed = downloaded.failed;
+        for (variant, path) in downloaded.files.into_iter().collect::<BTreeMap<_, _>>() {
+            let copied = self.layout.get_path(target, &font.install_spec, &variant, &path)
+                .and_then(|target_path| Ok(self.copy(&path, &target_path).map(|_| target_path)?));
+            match copied {
+                Ok(target_path) => {
+                    files.insert(variant, target_path);
+                },
+                Err(error) => failed.push(VariantFailure { variant, error })
+            }
+        }
+        for (variant, subsets) in downloaded.subset_files.into_iter().collect::<BTreeMap<_, _>>() {
+            let mut installed = BTreeMap::new();
+            for (subset, path) in subsets.into_iter().collect::<BTreeMap<_, _>>() {
+                let copied = self.layout.get_subset_path(target, &font.install_spec, &variant, &subset, &path)
+                    .and_then(|target_path| Ok(self.copy(&path, &target_path).map(|_| target_path)?));
+                match copied {
+                    Ok(target_path) => {
+                        installed.insert(subset, target_path);
+                    },
+                    Err(error) => failed.push(VariantFailure { variant, error })
+                }
+            }
+            subset_files.insert(variant, installed);
+        }
+        if !failed.is_empty() {
+            failed.sort_by_key(|v| v.variant);
+            return Err(FontFailure::new(font, FontError::Variants { installed: files, failed }))
+        }
+
+        let mut licenses = BTreeMap::new();
+        for (name, path) in downloaded.licenses.into_iter().collect::<BTreeMap<_, _>>() {
+            let copied = self.layout.get_license_path(target, &font.install_spec, &name)
+                .and_then(|target_path| Ok(self.copy(&path, &target_path).map(|_| target_path)?));
+            let target_path = match copied {
+                Ok(v) => v,
+                Err(error) => {
+                    let source_name = font.source.name.clone();
+                    return Err(FontFailure::new(font, FontError::License { source_name, error }))
+                }
+            };
+            licenses.insert(name, target_path);
+        }
+
+        let stylesheet = if self.generate_css {
+            let stylesheet = match self.layout.get_misc_path(target, &font.description, format!("{}.css", font.description.id)) {
+                Ok(v) => v,
+                Err(e) => {
+                    let error = std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string());
+                    return Err(FontFailure::new(font, FontError::Stylesheet(GenerateError::Write(error))))
+                }
+            };
+            let generate = if subset_files.is_empty() {
+                Generate::from_font(&stylesheet, &font.description, files.clone())
+            } else {
+                let subsets: HashMap<&String, &FontSubset> = font.install_spec.subsets.iter().map(|v| (&v.name, v)).collect();
+                Generate::from_subsets(&stylesheet, &font.description, subset_files.iter().flat_map(|(variant, files)| {
+                    let subsets = &subsets;
+                    files.iter().map(move |(subset, path)| {
+                        let subset = subsets.get(subset).map_or_else(|| FontSubset::new(subset, None), |v| (*v).clone());
+                        (*variant, subset, path)
+                    })
+                }))
+            };
+            let generated = generate.generate_css()
+                .and_then(|css| std::fs::write(&stylesheet, css).map_err(GenerateError::Write));
+            if let Err(e) = generated {
+                return Err(FontFailure::new(font, FontError::Stylesheet(e)))
+            }
+            Some(stylesheet)
+        } else {
+            None
+        };
+
+        Ok(InstalledFont {
+            font,
+            files,
+            subset_files,
+            licenses,
+            stylesheet
+        })
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        self.emit(InstallEvent::Copying { from, to });
+        copy_file(from, to)
+    }
+}
+
+fn copy_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        create_dir_all(parent)?;
+    }
+    copy(from, to).map(|_| ())
+}
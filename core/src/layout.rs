@@ -0,0 +1,128 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription};
+
+/// How installed font files are organised in the target directory.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// `<dir>/<font id>-<variant>.<ext>`, where the variant is e.g. `regular`, `700-italic` or `variable`
+    Flat,
+    /// `<dir>/<font id>/<font id>-<variant>.<ext>`
+    #[default]
+    FlatDirectory,
+}
+
+impl Layout {
+    /// The path a font file is installed to.
+    /// Font IDs come from the source, so IDs that aren't a single file name (like `..` or `a/b`) are an error.
+    pub fn get_path(&self, base_dir: impl AsRef<Path>, font_spec: &DefinedFontInstallSpec, variant_spec: &DefinedFontVariantSpec, source_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        self.path_for(base_dir.as_ref(), font_spec, variant_spec, None, source_path.as_ref())
+    }
+    /// Like [`Layout::get_path`], but for the file of a single subset, which gets the subset name as a suffix (e.g. `-latin`).
+    pub fn get_subset_path(&self, base_dir: impl AsRef<Path>, font_spec: &DefinedFontInstallSpec, variant_spec: &DefinedFontVariantSpec, subset: &str, source_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        self.path_for(base_dir.as_ref(), font_spec, variant_spec, Some(subset), source_path.as_ref())
+    }
+    fn path_for(&self, base_dir: &Path, font_spec: &DefinedFontInstallSpec, variant_spec: &DefinedFontVariantSpec, subset: Option<&str>, source_path: &Path) -> Result<PathBuf, Error> {
+        let id = check_file_name(&font_spec.id)?;
+        let ext = source_path.extension().map(|v| String::from(".") + v.to_str().unwrap()).unwrap_or("".to_string());
+        let subset = subset.map_or(String::new(), |v| format!("-{}", v));
+        let file_name = format!("{}{}{}{}", font_spec.id, {
+            if variant_spec == &DefinedFontVariantSpec::REGULAR {
+                "-regular".to_string()
+            } else {
+                let style = match variant_spec.style {
+                    DefinedFontStyle::Regular => "",
+                    DefinedFontStyle::Italic => "-italic",
+                };
+                match variant_spec.weight {
+                    DefinedFontWeight::REGULAR => style.to_string(),
+                    DefinedFontWeight::Fixed(weight) => "-".to_string() + weight.to_string().as_str() + style,
+                    DefinedFontWeight::Variable => "-variable".to_string() + style
+                }
+            }
+        }, subset, ext);
+        let file_name = check_file_name(&file_name)?;
+        Ok(match self {
+            Self::Flat => {
+                base_dir.join(file_name)
+            }
+            Self::FlatDirectory => {
+                base_dir.join(id).join(file_name)
+            }
+        })
+    }
+    /// The path of a license document of a font: next to the fonts in [`Layout::FlatDirectory`], and prefixed with the font ID in [`Layout::Flat`].
+    /// Only the file name of `name` is used, since it comes from the source; names without one (like `..`) are an error.
+    pub fn get_license_path(&self, base_dir: impl AsRef<Path>, font_spec: &DefinedFontInstallSpec, name: &str) -> Result<PathBuf, Error> {
+        let base_dir = base_dir.as_ref();
+        let id = check_file_name(&font_spec.id)?;
+        let name = Path::new(name).file_name()
+            .ok_or_else(|| Error::Generic(format!("invalid license name {:?}", name)))?;
+        Ok(match self {
+            Self::Flat => base_dir.join(format!("{}-{}", id, name.to_string_lossy())),
+            Self::FlatDirectory => base_dir.join(id).join(name)
+        })
+    }
+    pub fn get_misc_path(&self, base_dir: impl AsRef<Path>, font_desc: &FontDescription, name: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let base_dir = base_dir.as_ref();
+        let id = check_file_name(&font_desc.id)?;
+        let name = name.as_ref();
+        Ok(match self {
+            Self::Flat => base_dir.join(name),
+            Self::FlatDirectory => base_dir.join(id).join(name)
+        })
+    }
+}
+
+/// Checks that `name` is a single, normal path component, so that joining it to a directory stays inside of it.
+fn check_file_name(name: &str) -> Result<&str, Error> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(name),
+        _ => Err(Error::Generic(format!("invalid file name {:?}", name)))
+    }
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Self::Flat),
+            "flat-directory" => Ok(Self::FlatDirectory),
+            _ => Err(Error::Deserialisation(format!("No such layout: {}", s)))
+        }
+    }
+}
+impl Display for Layout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Flat => "flat",
+            Self::FlatDirectory => "flat-directory"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(id: &str) -> DefinedFontInstallSpec {
+        DefinedFontInstallSpec::new(id, [DefinedFontVariantSpec::REGULAR])
+    }
+
+    #[test]
+    fn paths_stay_in_the_target() {
+        for layout in [Layout::Flat, Layout::FlatDirectory] {
+            let path = layout.get_path("fonts", &spec("acme-sans"), &DefinedFontVariantSpec::REGULAR, "a.ttf").unwrap();
+            assert!(path.starts_with("fonts"));
+            for id in ["../../x", "a/b", "/tmp", "..", ""] {
+                assert!(layout.get_path("fonts", &spec(id), &DefinedFontVariantSpec::REGULAR, "a.ttf").is_err(), "{}", id);
+                assert!(layout.get_license_path("fonts", &spec(id), "LICENSE.txt").is_err(), "{}", id);
+            }
+            assert!(layout.get_subset_path("fonts", &spec("acme-sans"), &DefinedFontVariantSpec::REGULAR, "../x", "a.ttf").is_err());
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! The install pipeline used by the `fontpm` command line tool.
+//!
+//! Fonts are installed using an [`Installer`]:
+//!
+//! ```ignore
+//! let report = Installer::new(&host)
+//!     .sources(sources)
+//!     .add(FontSpec::parse("google-fonts:noto-sans")?)
+//!     .target("web/fonts")
+//!     .layout(Layout::Flat)
+//!     .generate_css(true)
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! Nothing in this crate prints anything; progress is reported through [`Installer::observe`] instead.
+
+pub mod config;
+pub mod error;
+pub mod generate;
+pub mod host;
+pub mod install;
+pub mod layout;
+pub mod spec;
+
+pub use error::{Error, Result};
+pub use install::{FontError, FontFailure, InstallEvent, InstalledFont, Installer, InstallReport, ResolvedFont, ResolveFailure, SelectedFont, SkippedFont, SkipReason, VariantFailure};
+pub use layout::Layout;
+pub use spec::{FontSelector, FontSpec};
+
+pub const VERSION_STR: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,137 @@
+use std::fmt::{Display, Formatter};
+use fontpm_api::Error;
+
+/// A font as specified by the user: either `<font ID>` or `<source ID>:<font ID>`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct FontSpec {
+    pub source: Option<String>,
+    pub font_id: String
+}
+
+impl FontSpec {
+    pub fn new(source: Option<impl ToString>, font_id: impl ToString) -> Self {
+        FontSpec {
+            source: source.map(|v| v.to_string()),
+            font_id: font_id.to_string()
+        }
+    }
+
+    pub fn parse<S>(v: S) -> Result<FontSpec, Error> where S: ToString {
+        let v = v.to_string();
+        if v.is_empty() {
+            return Err(Error::Generic("Fontspec must not be an empty string".into()))
+        }
+
+        let mut source = None;
+        let mut current = String::new();
+        for c in v.chars() {
+            if c == ':' {
+                if source.is_some() {
+                    return Err(Error::Generic("Character ':' is illegal in font ID".into()))
+                }
+                source = Some(current.clone());
+                current.clear();
+                continue
+            }
+            current.push(c)
+        }
+
+        if current.is_empty() {
+            return Err(Error::Generic("Font ID must not be empty".into()))
+        }
+
+        Ok(FontSpec {
+            source,
+            font_id: current
+        })
+    }
+}
+
+impl Display for FontSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}:{}", source, self.font_id),
+            None => f.write_str(&self.font_id)
+        }
+    }
+}
+
+/// A group of fonts as specified by the user: `tag:<tag>`, with more tags joined by `+` (e.g. `tag:serif+tag:display`),
+/// optionally prefixed with `<source ID>:`. Fonts must have every tag to be in the group.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct FontSelector {
+    pub source: Option<String>,
+    pub tags: Vec<String>
+}
+
+impl FontSelector {
+    const TAG_PREFIX: &'static str = "tag:";
+
+    /// Whether `v` is a selector rather than a [`FontSpec`].
+    pub fn is_selector(v: &str) -> bool {
+        v.starts_with(Self::TAG_PREFIX)
+            || v.split_once(':').is_some_and(|(_, rest)| rest.starts_with(Self::TAG_PREFIX))
+    }
+
+    pub fn parse<S>(v: S) -> Result<FontSelector, Error> where S: ToString {
+        let v = v.to_string();
+        let (source, tags) = if v.starts_with(Self::TAG_PREFIX) {
+            (None, v.as_str())
+        } else {
+            match v.split_once(':') {
+                Some((source, tags)) if !source.is_empty() => (Some(source.to_string()), tags),
+                _ => return Err(Error::Generic("Selector must start with tag:".into()))
+            }
+        };
+
+        let tags = tags.split('+')
+            .map(|tag| match tag.strip_prefix(Self::TAG_PREFIX) {
+                Some(tag) if !tag.is_empty() && !tag.contains(':') => Ok(tag.to_string()),
+                Some(_) => Err(Error::Generic(format!("Invalid tag in selector: {}", tag))),
+                None => Err(Error::Generic(format!("Expected tag:<tag> in selector, got {}", tag)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(FontSelector { source, tags })
+    }
+}
+
+impl Display for FontSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}:", source)?;
+        }
+        let tags: Vec<String> = self.tags.iter().map(|v| format!("{}{}", Self::TAG_PREFIX, v)).collect();
+        f.write_str(&tags.join("+"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(FontSpec::parse("noto-sans").unwrap(), FontSpec::new(None::<String>, "noto-sans"));
+        assert_eq!(FontSpec::parse("google-fonts:noto-sans").unwrap(), FontSpec::new(Some("google-fonts"), "noto-sans"));
+        assert_eq!(FontSpec::parse("google-fonts:noto-sans").unwrap().to_string(), "google-fonts:noto-sans");
+        assert!(FontSpec::parse("").is_err());
+        assert!(FontSpec::parse("google-fonts:").is_err());
+        assert!(FontSpec::parse("a:b:c").is_err());
+    }
+
+    #[test]
+    fn parse_selector() {
+        assert!(FontSelector::is_selector("tag:monospace"));
+        assert!(FontSelector::is_selector("google-fonts:tag:monospace"));
+        assert!(!FontSelector::is_selector("google-fonts:noto-sans"));
+
+        assert_eq!(FontSelector::parse("tag:serif+tag:display").unwrap(), FontSelector { source: None, tags: vec!["serif".into(), "display".into()] });
+        let selector = FontSelector::parse("google-fonts:tag:monospace").unwrap();
+        assert_eq!(selector.source.as_deref(), Some("google-fonts"));
+        assert_eq!(selector.to_string(), "google-fonts:tag:monospace");
+        assert!(FontSelector::parse("tag:").is_err());
+        assert!(FontSelector::parse("tag:serif+display").is_err());
+        assert!(FontSelector::parse(":tag:serif").is_err());
+    }
+}
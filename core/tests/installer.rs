@@ -0,0 +1,295 @@
+use std::sync::{Arc, Mutex};
+use fontpm_api::{Error as ApiError, FpmHost, Source};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec};
+use fontpm_core::{Error, FontError, FontSelector, FontSpec, InstallEvent, Installer, Layout, SkipReason};
+use fontpm_testing::layout::{assert_file_contents, assert_installed};
+use fontpm_testing::source::{FixtureFamily, InMemorySource};
+use fontpm_testing::TempFpmHost;
+
+fn first<'host>() -> Box<dyn Source<'host> + 'host> {
+    Box::new(InMemorySource::new("first", "First")
+        .with_family(FixtureFamily::new("test-sans", "Test Sans", "1")
+            .with_variant(400, DefinedFontStyle::Regular)
+            .with_variant(700, DefinedFontStyle::Italic)
+            .with_tag("sans-serif")
+            .with_license("OFL.txt", "test-sans license")))
+}
+fn second<'host>() -> Box<dyn Source<'host> + 'host> {
+    Box::new(InMemorySource::new("second", "Second")
+        .with_family(FixtureFamily::new("test-sans", "Test Sans", "2")
+            .with_variant(400, DefinedFontStyle::Regular)
+            .with_tag("sans-serif")
+            .with_tag("display"))
+        .with_family(FixtureFamily::new("test-mono", "Test Mono", "1")
+            .with_variant(400, DefinedFontStyle::Regular)
+            .with_tag("monospace")))
+}
+
+fn spec(v: &str) -> FontSpec {
+    FontSpec::parse(v).unwrap()
+}
+
+#[tokio::test]
+async fn installs_flat_with_css() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let report = Installer::new(&host)
+        .source(first())
+        .add(spec("test-sans"))
+        .target(&target)
+        .layout(Layout::Flat)
+        .generate_css(true)
+        .observe({
+            let events = events.clone();
+            move |event| if let InstallEvent::Installing { font } = event {
+                events.lock().unwrap().push(font.spec.to_string());
+            }
+        })
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.target, target);
+    assert_eq!(*events.lock().unwrap(), vec!["test-sans"]);
+    assert_installed(&target, &["test-sans-700-italic.ttf", "test-sans-OFL.txt", "test-sans-regular.ttf", "test-sans.css"]);
+    assert_file_contents(target.join("test-sans-OFL.txt"), "test-sans license");
+    assert_file_contents(target.join("test-sans-regular.ttf"), "test-sans 400 regular");
+
+    let font = &report.fonts[0];
+    assert_eq!(font.font.source.id, "first");
+    assert_eq!(font.files[&DefinedFontVariantSpec::REGULAR], target.join("test-sans-regular.ttf"));
+    assert_eq!(font.licenses["OFL.txt"], target.join("test-sans-OFL.txt"));
+    let stylesheet = std::fs::read_to_string(font.stylesheet.as_ref().unwrap()).unwrap();
+    assert!(stylesheet.contains("url(\"test-sans-700-italic.ttf\")"));
+}
+
+#[tokio::test]
+async fn first_source_wins_unless_named() {
+    let host = TempFpmHost::new();
+
+    let report = Installer::new(&host)
+        .sources([first(), second()])
+        .add(spec("test-mono"))
+        .add(spec("test-sans"))
+        .add(spec("second:test-sans"))
+        .run()
+        .await
+        .unwrap();
+
+    let resolved: Vec<_> = report.fonts.iter()
+        .map(|v| (v.font.spec.to_string(), v.font.source.id.as_str(), v.font.description.version.as_str()))
+        .collect();
+    assert_eq!(resolved, vec![
+        ("test-mono".to_string(), "second", "1"),
+        ("test-sans".to_string(), "first", "1"),
+        ("second:test-sans".to_string(), "second", "2")
+    ]);
+    assert_eq!(report.target, host.font_install_dir());
+    assert_installed(host.font_install_dir(), &["test-mono/test-mono-regular.ttf", "test-sans/OFL.txt", "test-sans/test-sans-700-italic.ttf", "test-sans/test-sans-regular.ttf"]);
+}
+
+#[tokio::test]
+async fn selectors_expand_to_tagged_fonts() {
+    let host = TempFpmHost::new();
+    let installer = Installer::new(&host).sources([first(), second()]);
+    let expand = |selector: &str| {
+        let selector = FontSelector::parse(selector).unwrap();
+        let installer = &installer;
+        async move {
+            installer.expand(&selector).await.map(|fonts| fonts.into_iter().map(|v| (v.spec.to_string(), v.size)).collect::<Vec<_>>())
+        }
+    };
+
+    assert_eq!(expand("tag:sans-serif").await.unwrap(), vec![("first:test-sans".to_string(), Some(41))]);
+    assert_eq!(expand("second:tag:sans-serif").await.unwrap(), vec![("second:test-sans".to_string(), Some(21))]);
+    assert_eq!(expand("tag:sans-serif+tag:display").await.unwrap(), vec![("second:test-sans".to_string(), Some(21))]);
+    assert!(expand("tag:monospace+tag:display").await.unwrap().is_empty());
+    assert!(matches!(expand("missing:tag:monospace").await, Err(Error::API(_))));
+
+    let mut installer = Installer::new(&host).sources([first(), second()]);
+    for font in installer.expand(&FontSelector::parse("tag:monospace").unwrap()).await.unwrap() {
+        installer = installer.add(font.spec);
+    }
+    let report = installer.run().await.unwrap();
+    assert_eq!(report.fonts[0].font.spec, spec("second:test-mono"));
+}
+
+#[tokio::test]
+async fn nothing_is_installed_if_resolving_fails() {
+    let host = TempFpmHost::new();
+
+    let result = Installer::new(&host)
+        .sources([first(), second()])
+        .add(spec("test-sans"))
+        .add(spec("nope"))
+        .add(spec("missing:test-sans"))
+        .run()
+        .await;
+
+    match result {
+        Err(Error::Resolve(failures)) => {
+            assert_eq!(failures.len(), 2);
+            assert_eq!(failures[0].spec, spec("nope"));
+            assert_eq!(failures[0].sources, vec!["First", "Second"]);
+            assert!(matches!(failures[0].error, ApiError::NoSuchFamily(_)));
+            assert_eq!(failures[1].spec, spec("missing:test-sans"));
+            assert!(failures[1].sources.is_empty());
+        },
+        other => panic!("expected a resolve error, got {:?}", other.map(|v| v.fonts.len()))
+    }
+    assert_installed(host.font_install_dir(), &[] as &[&str]);
+
+    assert!(matches!(Installer::new(&host).add(spec("test-sans")).run().await, Err(Error::NoSources)));
+}
+
+#[tokio::test]
+async fn keep_going_reports_every_font() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web");
+    // a directory where a font file should go makes copying that variant fail
+    std::fs::create_dir_all(target.join("test-mono-regular.ttf")).unwrap();
+
+    let report = Installer::new(&host)
+        .sources([first(), second()])
+        .add(spec("test-sans"))
+        .add(spec("first:test-sans"))
+        .add(spec("nope"))
+        .add(spec("test-mono"))
+        .target(&target)
+        .layout(Layout::Flat)
+        .keep_going(true)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(!report.is_complete());
+    assert_eq!(report.fonts.len(), 1);
+    assert_eq!(report.fonts[0].font.spec, spec("test-sans"));
+
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].font.spec, spec("first:test-sans"));
+    assert!(matches!(&report.skipped[0].reason, SkipReason::Duplicate(other) if *other == spec("test-sans")));
+
+    let failed: Vec<_> = report.failed.iter().map(|v| v.spec.to_string()).collect();
+    assert_eq!(failed, vec!["nope", "test-mono"]);
+    assert!(matches!(report.failed[0].error, FontError::Resolve { .. }));
+    match &report.failed[1].error {
+        FontError::Variants { installed, failed } => {
+            assert!(installed.is_empty());
+            assert_eq!(failed.len(), 1);
+            assert_eq!(failed[0].variant, DefinedFontVariantSpec::REGULAR);
+        },
+        other => panic!("expected a variant failure, got {}", other)
+    }
+    assert_installed(&target, &["test-sans-700-italic.ttf", "test-sans-OFL.txt", "test-sans-regular.ttf"]);
+}
+
+#[tokio::test]
+async fn variants_that_fail_to_download_are_reported() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web");
+    let source = InMemorySource::new("partial", "Partial")
+        .with_family(FixtureFamily::new("test-sans", "Test Sans", "1")
+            .with_variant(400, DefinedFontStyle::Regular)
+            .with_unavailable_variant(700, DefinedFontStyle::Italic))
+        .with_family(FixtureFamily::new("test-mono", "Test Mono", "1")
+            .with_unavailable_variant(400, DefinedFontStyle::Regular));
+
+    let report = Installer::new(&host)
+        .source(Box::new(source))
+        .add(spec("test-sans"))
+        .add(spec("test-mono"))
+        .target(&target)
+        .layout(Layout::Flat)
+        .keep_going(true)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(report.fonts.is_empty());
+    match &report.failed[0].error {
+        FontError::Variants { installed, failed } => {
+            assert_eq!(installed.keys().collect::<Vec<_>>(), vec![&DefinedFontVariantSpec::REGULAR]);
+            assert_eq!(failed.len(), 1);
+            assert!(matches!(failed[0].error, ApiError::ConnectionError(_)));
+        },
+        other => panic!("expected a variant failure, got {}", other)
+    }
+    // a font without any variant that could be downloaded fails as a whole
+    assert!(matches!(report.failed[1].error, FontError::Download { .. }));
+    assert_installed(&target, &["test-sans-regular.ttf"]);
+}
+
+#[tokio::test]
+async fn variants_are_not_retried_without_keep_going() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web");
+    let source = InMemorySource::new("partial", "Partial")
+        .with_family(FixtureFamily::new("test-sans", "Test Sans", "1")
+            .with_variant(400, DefinedFontStyle::Regular)
+            .with_unavailable_variant(700, DefinedFontStyle::Italic));
+
+    let result = Installer::new(&host)
+        .source(Box::new(source))
+        .add(spec("test-sans"))
+        .target(&target)
+        .layout(Layout::Flat)
+        .run()
+        .await;
+
+    assert!(matches!(result, Err(Error::Download { error: ApiError::ConnectionError(_), .. })));
+    assert_installed(&target, &[] as &[&str]);
+}
+
+#[tokio::test]
+async fn stops_at_first_failure_by_default() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web");
+    std::fs::create_dir_all(target.join("test-mono-regular.ttf")).unwrap();
+
+    let result = Installer::new(&host)
+        .source(second())
+        .add(spec("test-mono"))
+        .add(spec("test-sans"))
+        .target(&target)
+        .layout(Layout::Flat)
+        .run()
+        .await;
+
+    assert!(matches!(result, Err(Error::API(ApiError::IO(_)))));
+    assert_installed(&target, &[] as &[&str]);
+}
+
+#[tokio::test]
+async fn license_names_stay_in_the_target() {
+    let host = TempFpmHost::new();
+    let target = host.path().join("web").join("fonts");
+    let family = || FixtureFamily::new("test-sans", "Test Sans", "1")
+        .with_variant(400, DefinedFontStyle::Regular);
+    let source = InMemorySource::new("sneaky", "Sneaky")
+        .with_family(family()
+            .with_license("../../escaped.txt", "relative")
+            .with_license("/tmp/absolute.txt", "absolute"));
+
+    Installer::new(&host)
+        .source(Box::new(source))
+        .add(spec("test-sans"))
+        .target(&target)
+        .run()
+        .await
+        .unwrap();
+    assert_installed(&target, &["test-sans/absolute.txt", "test-sans/escaped.txt", "test-sans/test-sans-regular.ttf"]);
+    assert!(!host.path().join("escaped.txt").exists());
+
+    let source = InMemorySource::new("sneaky", "Sneaky")
+        .with_family(family().with_license("..", "parent"));
+    let result = Installer::new(&host)
+        .source(Box::new(source))
+        .add(spec("test-sans"))
+        .target(host.path().join("other"))
+        .run()
+        .await;
+    assert!(matches!(result, Err(Error::Download { .. })), "expected a license error, got {:?}", result.map(|v| v.fonts.len()));
+}
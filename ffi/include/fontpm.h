@@ -0,0 +1,126 @@
+/* Generated from ffi/src/lib.rs by cbindgen -- do not edit by hand. */
+
+#ifndef FONTPM_H
+#define FONTPM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+// The result of a call.
+typedef enum FpmStatus {
+  FPM_STATUS_OK = 0,
+  // A pointer was null, or a string was not valid UTF-8 or JSON of the expected shape.
+  FPM_STATUS_INVALID_ARGUMENT = 1,
+  // The configuration could not be loaded, or a source in it could not be created.
+  FPM_STATUS_CONFIG = 2,
+  // None of the enabled sources are available.
+  FPM_STATUS_NO_SOURCES = 3,
+  // One or more fonts could not be resolved.
+  FPM_STATUS_RESOLVE = 4,
+  // A font could not be downloaded.
+  FPM_STATUS_DOWNLOAD = 5,
+  // A file could not be read or written.
+  FPM_STATUS_IO = 6,
+  // A source could not connect to its server.
+  FPM_STATUS_CONNECTION = 7,
+  // Any other error.
+  FPM_STATUS_OTHER = 8,
+  // FontPM panicked. The context should not be used anymore.
+  FPM_STATUS_PANIC = 9,
+} FpmStatus;
+
+// A FontPM instance. Contexts must not be used from more than one thread at a time.
+typedef struct FpmContext FpmContext;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the version of FontPM, e.g. `"0.2.0"`. The string must not be freed.
+const char *fpm_version(void);
+
+// Returns a JSON object describing the last error on this thread, or null if the last call succeeded.
+//
+// The object has a numeric `status`, a `message`, and a list of `failures` (each with a `subject` and a `message`).
+// The string is owned by FontPM and is valid until the next call on this thread.
+const char *fpm_last_error(void);
+
+// Creates a context.
+//
+// `options_json` may be null, or a JSON object with these optional fields:
+// - `config`: the configuration to use instead of the configuration file, in the same shape as `config.toml`.
+//
+// # Safety
+//
+// `options_json` must be null or a NUL-terminated string, and `out_context` must be a valid pointer.
+// The context must be freed using `fpm_context_free`.
+enum FpmStatus fpm_context_new(const char *options_json,
+                               struct FpmContext **out_context);
+
+// Frees a context. Does nothing if `context` is null.
+//
+// # Safety
+//
+// `context` must be null or have been created by `fpm_context_new`, and must not be used afterwards.
+void fpm_context_free(struct FpmContext *context);
+
+// Frees a string returned by FontPM. Does nothing if `string` is null.
+//
+// # Safety
+//
+// `string` must be null or have been returned through an `out_json` parameter, and must not be used afterwards.
+void fpm_string_free(char *string);
+
+// Refreshes the index of every enabled source.
+//
+// On success, `*out_json` is set to an object with a list of `sources`, each with an `id`, a `name`,
+// and a `status` of either `"downloaded"` or `"already-up-to-date"`.
+// If any source fails, every failure is listed in the error.
+//
+// # Safety
+//
+// `context` must be a valid context and `out_json` must be a valid pointer.
+enum FpmStatus fpm_refresh(struct FpmContext *context,
+                           bool force,
+                           char **out_json);
+
+// Resolves fonts without installing them.
+//
+// `request_json` is an object with a list of `fonts` (fontspecs such as `"noto-sans"` or `"google-fonts:noto-sans"`).
+// On success, `*out_json` is set to an object with a list of `fonts`,
+// each with the `spec`, the `source` and `font` it resolved to, and its `variants`.
+//
+// # Safety
+//
+// `context` must be a valid context, `request_json` must be a NUL-terminated string and `out_json` must be a valid pointer.
+enum FpmStatus fpm_resolve(struct FpmContext *context,
+                           const char *request_json,
+                           char **out_json);
+
+// Resolves and installs fonts.
+//
+// `request_json` is an object with a list of `fonts` (as in `fpm_resolve`) and these optional fields:
+// - `directory`: the directory to install to, instead of the configured font directory.
+// - `layout`: either `"flat"` or `"flat-directory"` (the default).
+// - `generate_css`: whether to generate a stylesheet for every font.
+// - `keep_going`: whether to attempt every font even if some fail.
+// - `subsets`: the subsets (e.g. `"latin"`) to install instead of the full fonts, for sources that have them.
+//
+// On success, `*out_json` is set to an object with the absolute `target` directory and a list of `fonts`,
+// which are described like in `fpm_resolve`, with the `path` of each variant, the paths of their `licenses` and the `stylesheet` (or null).
+// Subsets are listed as a variant each, with the name of the `subset`.
+// It also lists the fonts that were `skipped` (with a `reason`) and, if `keep_going` is set,
+// the fonts that `failed` (each with a `subject` and a `message`).
+//
+// # Safety
+//
+// `context` must be a valid context, `request_json` must be a NUL-terminated string and `out_json` must be a valid pointer.
+enum FpmStatus fpm_install(struct FpmContext *context,
+                           const char *request_json,
+                           char **out_json);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* FONTPM_H */
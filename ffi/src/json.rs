@@ -0,0 +1,226 @@
+//! The JSON documents passed to and returned from the C interface.
+
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription};
+use fontpm_api::source::SourceDescription;
+use fontpm_core::{InstalledFont, InstallReport, ResolvedFont};
+use fontpm_core::config::EntireConfig;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ContextOptions {
+    /// Used instead of the configuration file if set.
+    pub config: Option<EntireConfig>
+}
+
+#[derive(Deserialize)]
+pub struct ResolveRequest {
+    pub fonts: Vec<String>
+}
+
+#[derive(Deserialize)]
+pub struct InstallRequest {
+    pub fonts: Vec<String>,
+    #[serde(default)]
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub layout: Option<String>,
+    #[serde(default)]
+    pub generate_css: bool,
+    #[serde(default)]
+    pub keep_going: bool,
+    #[serde(default)]
+    pub subsets: Vec<String>
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Weight {
+    Fixed(u32),
+    Named(&'static str)
+}
+
+impl From<DefinedFontWeight> for Weight {
+    fn from(value: DefinedFontWeight) -> Self {
+        match value {
+            DefinedFontWeight::Fixed(w) => Weight::Fixed(w),
+            DefinedFontWeight::Variable => Weight::Named("variable")
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Variant {
+    pub weight: Weight,
+    pub style: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>
+}
+
+impl Variant {
+    fn new(variant: &DefinedFontVariantSpec, path: Option<&Path>) -> Self {
+        Variant {
+            weight: variant.weight.into(),
+            style: match variant.style {
+                DefinedFontStyle::Regular => "regular",
+                DefinedFontStyle::Italic => "italic"
+            },
+            subset: None,
+            path: path.map(path_string)
+        }
+    }
+    fn with_subset(mut self, subset: &str) -> Self {
+        self.subset = Some(subset.to_string());
+        self
+    }
+}
+
+#[derive(Serialize)]
+pub struct Source {
+    pub id: String,
+    pub name: String
+}
+
+impl From<&SourceDescription> for Source {
+    fn from(value: &SourceDescription) -> Self {
+        Source {
+            id: value.id.clone(),
+            name: value.name.clone()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Font {
+    pub id: String,
+    pub name: String,
+    pub version: String
+}
+
+impl From<&FontDescription> for Font {
+    fn from(value: &FontDescription) -> Self {
+        Font {
+            id: value.id.clone(),
+            name: value.name.clone(),
+            version: value.version.clone()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Resolved {
+    pub spec: String,
+    pub source: Source,
+    pub font: Font,
+    pub variants: Vec<Variant>
+}
+
+impl From<&ResolvedFont> for Resolved {
+    fn from(value: &ResolvedFont) -> Self {
+        Resolved {
+            spec: value.spec.to_string(),
+            source: (&value.source).into(),
+            font: (&value.description).into(),
+            variants: value.install_spec.styles.iter().map(|v| Variant::new(v, None)).collect()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Installed {
+    #[serde(flatten)]
+    pub font: Resolved,
+    pub licenses: Vec<String>,
+    pub stylesheet: Option<String>
+}
+
+impl From<&InstalledFont> for Installed {
+    fn from(value: &InstalledFont) -> Self {
+        let mut font = Resolved::from(&value.font);
+        font.variants = value.files.iter().map(|(variant, path)| Variant::new(variant, Some(path))).collect();
+        font.variants.extend(value.subset_files.iter().flat_map(|(variant, files)| {
+            files.iter().map(move |(subset, path)| Variant::new(variant, Some(path)).with_subset(subset))
+        }));
+        Installed {
+            font,
+            licenses: value.licenses.values().map(|v| path_string(v)).collect(),
+            stylesheet: value.stylesheet.as_deref().map(path_string)
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResolveResponse {
+    pub fonts: Vec<Resolved>
+}
+
+#[derive(Serialize)]
+pub struct Skipped {
+    #[serde(flatten)]
+    pub font: Resolved,
+    pub reason: String
+}
+
+#[derive(Serialize)]
+pub struct InstallResponse {
+    pub target: String,
+    pub fonts: Vec<Installed>,
+    pub skipped: Vec<Skipped>,
+    pub failed: Vec<Failure>
+}
+
+impl From<&InstallReport> for InstallResponse {
+    fn from(value: &InstallReport) -> Self {
+        InstallResponse {
+            target: path_string(&value.target),
+            fonts: value.fonts.iter().map(Installed::from).collect(),
+            skipped: value.skipped.iter()
+                .map(|v| Skipped { font: (&v.font).into(), reason: v.reason.to_string() })
+                .collect(),
+            failed: value.failed.iter()
+                .map(|v| Failure { subject: v.spec.to_string(), message: v.error.to_string() })
+                .collect()
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefreshStatus {
+    Downloaded,
+    AlreadyUpToDate
+}
+
+#[derive(Serialize)]
+pub struct Refreshed {
+    #[serde(flatten)]
+    pub source: Source,
+    pub status: RefreshStatus
+}
+
+#[derive(Serialize)]
+pub struct RefreshResponse {
+    pub sources: Vec<Refreshed>
+}
+
+/// Why a font or source failed.
+#[derive(Serialize)]
+pub struct Failure {
+    /// The font spec or source ID that failed.
+    pub subject: String,
+    pub message: String
+}
+
+#[derive(Serialize)]
+pub struct ErrorResponse<'a> {
+    pub status: u32,
+    pub message: &'a str,
+    pub failures: &'a [Failure]
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
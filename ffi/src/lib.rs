@@ -0,0 +1,382 @@
+//! A C interface to FontPM.
+//!
+//! Every function returns an [`FpmStatus`]. Results are returned as JSON strings, which must be freed using [`fpm_string_free`].
+//! When a function fails, a JSON description of the error can be read using [`fpm_last_error`].
+//! The header for this interface is `include/fontpm.h`.
+//!
+//! Only built-in sources are available; source plugins are not loaded.
+
+mod json;
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use fontpm_api::Source;
+use fontpm_api::registry;
+use fontpm_api::source::RefreshOutput;
+use fontpm_core::{FontSpec, Installer, Layout};
+use fontpm_core::config::EntireConfig;
+use fontpm_core::host::FpmHostImpl;
+// the same built-in sources as the CLI, which are only registered if their crates are linked in
+#[cfg(feature = "google-fonts")]
+use fontpm_source_google_fonts as _;
+#[cfg(feature = "local")]
+use fontpm_source_local as _;
+#[cfg(feature = "registry")]
+use fontpm_source_registry as _;
+use crate::json::Failure;
+
+/// The result of a call.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FpmStatus {
+    Ok = 0,
+    /// A pointer was null, or a string was not valid UTF-8 or JSON of the expected shape.
+    InvalidArgument = 1,
+    /// The configuration could not be loaded, or a source in it could not be created.
+    Config = 2,
+    /// None of the enabled sources are available.
+    NoSources = 3,
+    /// One or more fonts could not be resolved.
+    Resolve = 4,
+    /// A font could not be downloaded.
+    Download = 5,
+    /// A file could not be read or written.
+    Io = 6,
+    /// A source could not connect to its server.
+    Connection = 7,
+    /// Any other error.
+    Other = 8,
+    /// FontPM panicked. The context should not be used anymore.
+    Panic = 9
+}
+
+/// A FontPM instance. Contexts must not be used from more than one thread at a time.
+pub struct FpmContext {
+    runtime: tokio::runtime::Runtime,
+    host: FpmHostImpl
+}
+
+impl FpmContext {
+    /// The enabled sources that are built in. Sources that exist but can't be created (e.g. because of an unknown `type`) are an error.
+    fn sources(&self) -> Result<Vec<Box<dyn Source<'_> + '_>>, Error> {
+        let mut sources = Vec::new();
+        for id in &self.host.entire_config().fontpm().enabled_sources {
+            match registry::create_source(id, &self.host) {
+                Ok(Some(source)) => sources.push(source),
+                Ok(None) => {},
+                Err(e) => return Err(Error::new(FpmStatus::Config, e))
+            }
+        }
+        Ok(sources)
+    }
+}
+
+struct Error {
+    status: FpmStatus,
+    message: String,
+    failures: Vec<Failure>
+}
+
+impl Error {
+    fn new(status: FpmStatus, message: impl ToString) -> Self {
+        Error {
+            status,
+            message: message.to_string(),
+            failures: Vec::new()
+        }
+    }
+    fn invalid(message: impl ToString) -> Self {
+        Self::new(FpmStatus::InvalidArgument, message)
+    }
+}
+
+impl From<fontpm_api::Error> for Error {
+    fn from(value: fontpm_api::Error) -> Self {
+        let status = match value {
+            fontpm_api::Error::IO(_) => FpmStatus::Io,
+            fontpm_api::Error::ConnectionError(_) => FpmStatus::Connection,
+            fontpm_api::Error::NoSuchFamily(_) => FpmStatus::Resolve,
+            _ => FpmStatus::Other
+        };
+        Error::new(status, value)
+    }
+}
+impl From<fontpm_core::Error> for Error {
+    fn from(value: fontpm_core::Error) -> Self {
+        let message = value.to_string();
+        match value {
+            fontpm_core::Error::NoSources => Error::new(FpmStatus::NoSources, message),
+            fontpm_core::Error::Resolve(failures) => Error {
+                status: FpmStatus::Resolve,
+                message,
+                failures: failures.into_iter()
+                    .map(|v| Failure { subject: v.spec.to_string(), message: v.error.to_string() })
+                    .collect()
+            },
+            fontpm_core::Error::Download { .. } => Error::new(FpmStatus::Download, message),
+            fontpm_core::Error::API(e) => e.into(),
+            fontpm_core::Error::Generate(_) => Error::new(FpmStatus::Io, message)
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(error: Option<&Error>) {
+    let json = error.map(|error| {
+        let response = json::ErrorResponse {
+            status: error.status as u32,
+            message: &error.message,
+            failures: &error.failures
+        };
+        to_c_string(&response)
+    });
+    LAST_ERROR.with(|v| *v.borrow_mut() = json);
+}
+
+fn to_c_string(value: &impl Serialize) -> CString {
+    let json = serde_json::to_string(value).expect("responses are always serialisable");
+    // NOTE: JSON escapes control characters, so there can't be any NUL bytes
+    CString::new(json).unwrap()
+}
+
+/// Runs `f`, turning errors and panics into a status code and recording the error.
+fn call(f: impl FnOnce() -> Result<(), Error>) -> FpmStatus {
+    let result = catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(Error::new(FpmStatus::Panic, "FontPM panicked")));
+    match result {
+        Ok(()) => {
+            set_last_error(None);
+            FpmStatus::Ok
+        },
+        Err(e) => {
+            set_last_error(Some(&e));
+            e.status
+        }
+    }
+}
+
+unsafe fn read_str<'a>(v: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if v.is_null() {
+        return Err(Error::invalid(format!("{} must not be null", name)))
+    }
+    CStr::from_ptr(v).to_str().map_err(|_| Error::invalid(format!("{} is not valid UTF-8", name)))
+}
+
+unsafe fn read_json<T: DeserializeOwned>(v: *const c_char, name: &str) -> Result<T, Error> {
+    serde_json::from_str(read_str(v, name)?).map_err(|e| Error::invalid(format!("{} is invalid: {}", name, e)))
+}
+
+unsafe fn context<'a>(context: *mut FpmContext) -> Result<&'a FpmContext, Error> {
+    context.as_ref().ok_or_else(|| Error::invalid("context must not be null"))
+}
+
+unsafe fn write_json(out_json: *mut *mut c_char, value: &impl Serialize) -> Result<(), Error> {
+    if out_json.is_null() {
+        return Err(Error::invalid("out_json must not be null"))
+    }
+    *out_json = to_c_string(value).into_raw();
+    Ok(())
+}
+
+fn parse_specs(fonts: &[String]) -> Result<Vec<FontSpec>, Error> {
+    fonts.iter()
+        .map(|v| FontSpec::parse(v).map_err(|e| Error::invalid(format!("invalid fontspec \"{}\": {}", v, e))))
+        .collect()
+}
+
+/// Returns the version of FontPM, e.g. `"0.2.0"`. The string must not be freed.
+#[no_mangle]
+pub extern "C" fn fpm_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Returns a JSON object describing the last error on this thread, or null if the last call succeeded.
+///
+/// The object has a numeric `status`, a `message`, and a list of `failures` (each with a `subject` and a `message`).
+/// The string is owned by FontPM and is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn fpm_last_error() -> *const c_char {
+    LAST_ERROR.with(|v| v.borrow().as_ref().map_or(ptr::null(), |v| v.as_ptr()))
+}
+
+/// Creates a context.
+///
+/// `options_json` may be null, or a JSON object with these optional fields:
+/// - `config`: the configuration to use instead of the configuration file, in the same shape as `config.toml`.
+///
+/// # Safety
+///
+/// `options_json` must be null or a NUL-terminated string, and `out_context` must be a valid pointer.
+/// The context must be freed using `fpm_context_free`.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_context_new(options_json: *const c_char, out_context: *mut *mut FpmContext) -> FpmStatus {
+    call(|| {
+        if out_context.is_null() {
+            return Err(Error::invalid("out_context must not be null"))
+        }
+        let options: json::ContextOptions = if options_json.is_null() {
+            Default::default()
+        } else {
+            read_json(options_json, "options_json")?
+        };
+        let config = match options.config {
+            Some(v) => v,
+            None => EntireConfig::load().map_err(|e| Error::new(FpmStatus::Config, e))?
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::new(FpmStatus::Io, e))?;
+
+        let context = FpmContext {
+            runtime,
+            host: FpmHostImpl::from_config(config, None)
+        };
+        *out_context = Box::into_raw(Box::new(context));
+        Ok(())
+    })
+}
+
+/// Frees a context. Does nothing if `context` is null.
+///
+/// # Safety
+///
+/// `context` must be null or have been created by `fpm_context_new`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_context_free(context: *mut FpmContext) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+/// Frees a string returned by FontPM. Does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or have been returned through an `out_json` parameter, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Refreshes the index of every enabled source.
+///
+/// On success, `*out_json` is set to an object with a list of `sources`, each with an `id`, a `name`,
+/// and a `status` of either `"downloaded"` or `"already-up-to-date"`.
+/// If any source fails, every failure is listed in the error.
+///
+/// # Safety
+///
+/// `context` must be a valid context and `out_json` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_refresh(context: *mut FpmContext, force: bool, out_json: *mut *mut c_char) -> FpmStatus {
+    call(|| {
+        let context = self::context(context)?;
+        let sources = context.sources()?;
+        if sources.is_empty() {
+            return Err(fontpm_core::Error::NoSources.into())
+        }
+
+        let mut refreshed = Vec::new();
+        let mut error: Option<Error> = None;
+        for source in &sources {
+            match context.runtime.block_on(source.refresh(force)) {
+                Ok(output) => refreshed.push(json::Refreshed {
+                    source: (&source.description()).into(),
+                    status: match output {
+                        RefreshOutput::Downloaded => json::RefreshStatus::Downloaded,
+                        RefreshOutput::AlreadyUpToDate => json::RefreshStatus::AlreadyUpToDate
+                    }
+                }),
+                Err(e) => {
+                    let failure = Failure { subject: source.id().to_string(), message: e.to_string() };
+                    error.get_or_insert_with(|| Error::new(Error::from(e).status, "could not refresh every source"))
+                        .failures.push(failure);
+                }
+            }
+        }
+        if let Some(error) = error {
+            return Err(error)
+        }
+
+        write_json(out_json, &json::RefreshResponse { sources: refreshed })
+    })
+}
+
+/// Resolves fonts without installing them.
+///
+/// `request_json` is an object with a list of `fonts` (fontspecs such as `"noto-sans"` or `"google-fonts:noto-sans"`).
+/// On success, `*out_json` is set to an object with a list of `fonts`,
+/// each with the `spec`, the `source` and `font` it resolved to, and its `variants`.
+///
+/// # Safety
+///
+/// `context` must be a valid context, `request_json` must be a NUL-terminated string and `out_json` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_resolve(context: *mut FpmContext, request_json: *const c_char, out_json: *mut *mut c_char) -> FpmStatus {
+    call(|| {
+        let context = self::context(context)?;
+        let request: json::ResolveRequest = read_json(request_json, "request_json")?;
+
+        let mut installer = Installer::new(&context.host).sources(context.sources()?);
+        for spec in parse_specs(&request.fonts)? {
+            installer = installer.add(spec);
+        }
+        let resolved = context.runtime.block_on(installer.resolve())?;
+
+        write_json(out_json, &json::ResolveResponse { fonts: resolved.iter().map(Into::into).collect() })
+    })
+}
+
+/// Resolves and installs fonts.
+///
+/// `request_json` is an object with a list of `fonts` (as in `fpm_resolve`) and these optional fields:
+/// - `directory`: the directory to install to, instead of the configured font directory.
+/// - `layout`: either `"flat"` or `"flat-directory"` (the default).
+/// - `generate_css`: whether to generate a stylesheet for every font.
+/// - `keep_going`: whether to attempt every font even if some fail.
+/// - `subsets`: the subsets (e.g. `"latin"`) to install instead of the full fonts, for sources that have them.
+///
+/// On success, `*out_json` is set to an object with the absolute `target` directory and a list of `fonts`,
+/// which are described like in `fpm_resolve`, with the `path` of each variant, the paths of their `licenses` and the `stylesheet` (or null).
+/// Subsets are listed as a variant each, with the name of the `subset`.
+/// It also lists the fonts that were `skipped` (with a `reason`) and, if `keep_going` is set,
+/// the fonts that `failed` (each with a `subject` and a `message`).
+///
+/// # Safety
+///
+/// `context` must be a valid context, `request_json` must be a NUL-terminated string and `out_json` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fpm_install(context: *mut FpmContext, request_json: *const c_char, out_json: *mut *mut c_char) -> FpmStatus {
+    call(|| {
+        let context = self::context(context)?;
+        let request: json::InstallRequest = read_json(request_json, "request_json")?;
+
+        let mut installer = Installer::new(&context.host)
+            .sources(context.sources()?)
+            .generate_css(request.generate_css)
+            .keep_going(request.keep_going)
+            .subsets(request.subsets);
+        for spec in parse_specs(&request.fonts)? {
+            installer = installer.add(spec);
+        }
+        if let Some(directory) = request.directory {
+            installer = installer.target(directory);
+        }
+        if let Some(layout) = request.layout {
+            installer = installer.layout(layout.parse::<Layout>().map_err(Error::invalid)?);
+        }
+        let report = context.runtime.block_on(installer.run())?;
+
+        write_json(out_json, &json::InstallResponse::from(&report))
+    })
+}
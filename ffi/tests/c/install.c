@@ -0,0 +1,67 @@
+/*
+ * Installs fonts through the C interface, printing every JSON result on its own line.
+ * Usage: install <options json> <install request json>
+ */
+#include <stdio.h>
+#include <string.h>
+#include "fontpm.h"
+
+#define CHECK(call) do { \
+        FpmStatus status = (call); \
+        if (status != FPM_STATUS_OK) { \
+            fprintf(stderr, "%s failed with status %d: %s\n", #call, status, fpm_last_error()); \
+            return 1; \
+        } \
+    } while (0)
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s <options json> <install request json>\n", argv[0]);
+        return 2;
+    }
+
+    if (strlen(fpm_version()) == 0) {
+        fprintf(stderr, "fpm_version returned an empty string\n");
+        return 1;
+    }
+
+    FpmContext *context = NULL;
+    CHECK(fpm_context_new(argv[1], &context));
+
+    char *json = NULL;
+    CHECK(fpm_refresh(context, false, &json));
+    printf("%s\n", json);
+    fpm_string_free(json);
+
+    CHECK(fpm_install(context, argv[2], &json));
+    printf("%s\n", json);
+    fpm_string_free(json);
+
+    json = NULL;
+    FpmStatus status = fpm_resolve(context, "{\"fonts\": [\"no-such-font\"]}", &json);
+    if (status != FPM_STATUS_RESOLVE || json != NULL) {
+        fprintf(stderr, "resolving a missing font returned status %d\n", status);
+        return 1;
+    }
+    printf("%s\n", fpm_last_error());
+
+    status = fpm_install(context, "not json", &json);
+    if (status != FPM_STATUS_INVALID_ARGUMENT) {
+        fprintf(stderr, "installing with an invalid request returned status %d\n", status);
+        return 1;
+    }
+
+    fpm_context_free(context);
+
+    const char *misconfigured = "{\"config\": {\"fontpm\": {\"enabled_sources\": [\"broken\"]}, \"sources\": {\"broken\": {\"type\": \"no-such-type\"}}}}";
+    CHECK(fpm_context_new(misconfigured, &context));
+    json = NULL;
+    status = fpm_refresh(context, false, &json);
+    if (status != FPM_STATUS_CONFIG || json != NULL) {
+        fprintf(stderr, "refreshing a misconfigured source returned status %d\n", status);
+        return 1;
+    }
+    printf("%s\n", fpm_last_error());
+    fpm_context_free(context);
+    return 0;
+}
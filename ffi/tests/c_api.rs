@@ -0,0 +1,127 @@
+#![cfg(all(unix, feature = "google-fonts"))]
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+use serde_json::{json, Value};
+use fontpm_testing::google_fonts_fixture_server;
+use fontpm_testing::google_fonts::FILES;
+use fontpm_testing::layout::{assert_file_contents, assert_installed};
+
+/// The directory containing the compiled library, which is next to the test executable.
+fn library_dir() -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().to_path_buf()
+}
+
+/// Compiles and runs `tests/c/install.c` against a fixture Google Fonts server.
+#[test]
+fn c_program() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    let executable = dir.join("install");
+
+    // a compiler that was asked for has to work, but the default one is optional
+    let explicit_cc = env::var("CC").ok();
+    let cc = explicit_cc.clone().unwrap_or_else(|| "cc".to_string());
+    let compiled = Command::new(&cc)
+        .arg(crate_dir.join("tests").join("c").join("install.c"))
+        .arg("-I").arg(crate_dir.join("include"))
+        .arg("-L").arg(library_dir())
+        .arg("-lfontpm_ffi")
+        .arg("-o").arg(&executable)
+        .status();
+    match compiled {
+        Err(e) if explicit_cc.is_some() => panic!("could not run the C compiler in CC ({}): {}", cc, e),
+        Err(e) => {
+            eprintln!("skipping: could not run C compiler {} (set CC to require one): {}", cc, e);
+            return
+        },
+        Ok(status) => assert!(status.success(), "could not compile install.c")
+    }
+
+    let server = google_fonts_fixture_server();
+    // a registry source, to check that the same sources are built in as in the CLI
+    let registry = dir.join("registry");
+    std::fs::create_dir_all(&registry).unwrap();
+    std::fs::write(registry.join("AcmeSans-Regular.ttf"), "acme sans").unwrap();
+    std::fs::write(registry.join("index.json"), json!({
+        "families": {
+            "acme-sans": {"name": "ACME Sans", "version": "1", "variants": {"regular": {"path": "AcmeSans-Regular.ttf"}}}
+        }
+    }).to_string()).unwrap();
+
+    let options = json!({
+        "config": {
+            "fontpm": {
+                "enabled_sources": ["google-fonts", "acme"],
+                "cache_dir": dir.join("cache"),
+                "font_install_dir": dir.join("fonts")
+            },
+            "sources": {
+                "google-fonts": {
+                    "index_url": server.url_for("/data/google-fonts.json"),
+                    "commit_url": server.url_for("/branches/data"),
+                    "file_base_url": server.url_for("/")
+                },
+                "acme": {
+                    "type": "registry",
+                    "index": registry.join("index.json")
+                }
+            }
+        }
+    });
+    let target = dir.join("web");
+    let request = json!({
+        "fonts": ["google-fonts:test-sans", "acme:acme-sans"],
+        "directory": target,
+        "layout": "flat",
+        "generate_css": true
+    });
+
+    let output = Command::new(&executable)
+        .arg(options.to_string())
+        .arg(request.to_string())
+        .env("LD_LIBRARY_PATH", library_dir())
+        .env("DYLD_LIBRARY_PATH", library_dir())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "install failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let lines: Vec<Value> = String::from_utf8(output.stdout).unwrap()
+        .lines()
+        .map(|v| serde_json::from_str(v).unwrap())
+        .collect();
+    assert_eq!(lines[0], json!({"sources": [
+        {"id": "google-fonts", "name": "Google Fonts", "status": "downloaded"},
+        {"id": "acme", "name": "acme", "status": "downloaded"}
+    ]}));
+
+    let installed = &lines[1];
+    assert_eq!(installed["target"], json!(target));
+    assert_eq!(installed["fonts"][0]["font"], json!({"id": "test-sans", "name": "Test Sans", "version": "3"}));
+    assert_eq!(installed["fonts"][0]["variants"][0], json!({"weight": "variable", "style": "regular", "path": target.join("test-sans-variable.ttf")}));
+    assert_eq!(installed["fonts"][0]["variants"][2], json!({"weight": 400, "style": "regular", "path": target.join("test-sans-regular.ttf")}));
+    assert_eq!(installed["fonts"][0]["licenses"], json!([target.join("test-sans-OFL.txt")]));
+    assert_eq!(installed["fonts"][1]["font"], json!({"id": "acme-sans", "name": "ACME Sans", "version": "1"}));
+    assert_installed(&target, &[
+        "acme-sans-regular.ttf",
+        "acme-sans.css",
+        "test-sans-700.ttf",
+        "test-sans-OFL.txt",
+        "test-sans-italic.ttf",
+        "test-sans-regular.ttf",
+        "test-sans-variable-italic.ttf",
+        "test-sans-variable.ttf",
+        "test-sans.css"
+    ]);
+    assert_file_contents(target.join("test-sans-700.ttf"), FILES[2]);
+
+    let error = &lines[2];
+    assert_eq!(error["status"], json!(4));
+    assert_eq!(error["failures"][0]["subject"], json!("no-such-font"));
+
+    let error = &lines[3];
+    assert_eq!(error["status"], json!(2));
+    assert!(error["message"].as_str().unwrap().contains("no-such-type"), "{}", error);
+}
@@ -0,0 +1,21 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Makes sure `include/fontpm.h` matches the code.
+/// Run with `FONTPM_UPDATE_HEADER=1` to regenerate the header instead.
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let header_path = crate_dir.join("include").join("fontpm.h");
+
+    let mut generated = Vec::new();
+    cbindgen::generate(&crate_dir).expect("could not generate header").write(&mut generated);
+
+    if std::env::var_os("FONTPM_UPDATE_HEADER").is_some() {
+        fs::write(&header_path, generated).unwrap();
+        return
+    }
+
+    let committed = fs::read(&header_path).unwrap_or_default();
+    assert!(committed == generated, "{} is out of date; regenerate it using `FONTPM_UPDATE_HEADER=1 cargo test -p fontpm-ffi --test header`", header_path.display());
+}
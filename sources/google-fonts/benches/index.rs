@@ -0,0 +1,90 @@
+//! Compares resolving fonts through the binary index with parsing `data.json`, which is what every resolve used to do.
+//!
+//! Run with `cargo bench -p fontpm-source-google-fonts`.
+
+use std::fs;
+use std::time::{Duration, Instant};
+use serde_json::{json, Map, Value};
+use fontpm_api::{FpmHost, Source};
+use fontpm_api::font::FontInstallSpec;
+use fontpm_source_google_fonts::{Data, GoogleFontsSource};
+use fontpm_testing::TempFpmHost;
+
+const FAMILIES: usize = 1600;
+const RESOLVES: usize = 10;
+const RUNS: u32 = 10;
+
+/// An index about the size of the real one.
+fn index() -> String {
+    let mut families = Map::new();
+    for i in 0..FAMILIES {
+        let id = format!("family-{}", i);
+        let variants = ["regular", "italic", "100", "100italic", "300", "300italic", "500", "500italic", "700", "700italic", "900", "900italic"];
+        let files: Map<String, Value> = variants.iter()
+            .map(|v| (v.to_string(), json!(format!("fonts.gstatic.com/s/family{}/v1/{}-{}.ttf", i, id, v))))
+            .collect();
+        families.insert(id.clone(), json!({
+            "id": id,
+            "display_name": format!("Family {}", i),
+            "version": 1,
+            "tags": ["sans-serif"],
+            "lastModified": 1672531200,
+            "files": files,
+            "variants": variants,
+            "variable_files": {"regular": format!("fonts.gstatic.com/s/family{}/v1/{}[wght].ttf", i, id)}
+        }));
+    }
+    let ids: Vec<String> = families.keys().cloned().collect();
+    json!({"families": families, "tags": {"sans-serif": ids}}).to_string()
+}
+
+/// Times `f` with a fresh value from `setup` on every run.
+fn time<T, S, F>(name: &str, mut setup: S, mut f: F) where S: FnMut() -> T, F: FnMut(T) {
+    let mut total = Duration::ZERO;
+    for _ in 0..RUNS {
+        let value = setup();
+        let start = Instant::now();
+        f(value);
+        total += start.elapsed();
+    }
+    println!("{:<48} {:>10.3} ms", name, total.as_secs_f64() * 1000.0 / RUNS as f64);
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let host = TempFpmHost::new();
+    let cache_dir = host.cache_dir_for(GoogleFontsSource::ID);
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("data.json"), index()).unwrap();
+
+    let specs: Vec<FontInstallSpec> = (0..RESOLVES)
+        .map(|i| FontInstallSpec::new_all_styles(format!("family-{}", i * FAMILIES / RESOLVES)))
+        .collect();
+    let resolve_all = |source: &GoogleFontsSource| runtime.block_on(async {
+        for spec in &specs {
+            source.resolve_font(spec).await.unwrap();
+        }
+    });
+
+    println!("{} families, {} resolves per run", FAMILIES, RESOLVES);
+    let new_source = || {
+        let mut source = GoogleFontsSource::new();
+        source.set_host(&host);
+        source
+    };
+
+    time("parsing data.json for every resolve", || (), |_| {
+        for _ in &specs {
+            let data = fs::read(cache_dir.join("data.json")).unwrap();
+            serde_json::from_slice::<Data>(&data).unwrap();
+        }
+    });
+    time("building the binary index", || {
+        fs::remove_file(cache_dir.join("data.bin")).ok();
+        new_source()
+    }, |source| resolve_all(&source));
+    time("loading the binary index", new_source, |source| resolve_all(&source));
+    let source = new_source();
+    resolve_all(&source);
+    time("with the index already loaded", || (), |_| resolve_all(&source));
+}
@@ -0,0 +1,61 @@
+//! Builds the binary index for the `bundled-index` feature from the index snapshot in `FONTPM_BUNDLED_INDEX`,
+//! so that it doesn't have to be parsed at runtime.
+
+// the index is built with the same code the source uses at runtime
+#[cfg(feature = "bundled-index")]
+#[allow(dead_code)]
+#[path = "src/data.rs"]
+mod data;
+#[cfg(feature = "bundled-index")]
+#[allow(dead_code)]
+#[path = "src/index.rs"]
+mod index;
+#[cfg(feature = "bundled-index")]
+#[allow(dead_code)]
+#[path = "src/schema.rs"]
+mod schema;
+
+fn main() {
+    #[cfg(feature = "bundled-index")]
+    bundle_index();
+}
+
+#[cfg(feature = "bundled-index")]
+fn bundle_index() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    println!("cargo:rerun-if-env-changed=FONTPM_BUNDLED_INDEX");
+    let target = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("google-fonts.index.gz");
+    let source = match env::var_os("FONTPM_BUNDLED_INDEX") {
+        Some(v) => PathBuf::from(v),
+        None => {
+            // an empty snapshot means there is none, so that `--all-features` builds still work
+            println!("cargo:warning=FONTPM_BUNDLED_INDEX is not set, so no index is bundled");
+            println!("cargo:rustc-env=FONTPM_BUNDLED_INDEX_TIME=0");
+            fs::write(target, []).unwrap();
+            return
+        }
+    };
+    println!("cargo:rerun-if-changed={}", source.display());
+
+    let json = fs::read(&source)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", source.display(), e));
+    let data = schema::parse(&json)
+        .unwrap_or_else(|e| panic!("{} is not a valid index: {}", source.display(), e));
+    let index = index::Index::encode(&data, index::Stamp::default())
+        .unwrap_or_else(|e| panic!("could not encode {}: {}", source.display(), e));
+    let mut encoder = GzEncoder::new(File::create(target).unwrap(), Compression::best());
+    encoder.write_all(&index).unwrap();
+    encoder.finish().unwrap();
+
+    // the snapshot is as old as the file it was taken from
+    let modified = fs::metadata(&source).and_then(|v| v.modified()).unwrap();
+    let modified = modified.duration_since(UNIX_EPOCH).map_or(0, |v| v.as_secs());
+    println!("cargo:rustc-env=FONTPM_BUNDLED_INDEX_TIME={}", modified);
+}
@@ -0,0 +1,55 @@
+//! The index snapshot embedded with the `bundled-index` feature, used until the index is first refreshed.
+//! It's built into a binary index by `build.rs`, and is empty if no snapshot was given.
+
+use std::io::Read;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use flate2::read::GzDecoder;
+use fontpm_api::Error;
+use crate::index::Index;
+
+static INDEX: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/google-fonts.index.gz"));
+const TIME: &str = env!("FONTPM_BUNDLED_INDEX_TIME");
+
+/// The bundled index, if there is one.
+pub fn index() -> Result<Option<Index>, Error> {
+    read(INDEX)
+}
+
+fn read(snapshot: &[u8]) -> Result<Option<Index>, Error> {
+    if snapshot.is_empty() {
+        return Ok(None)
+    }
+    let mut bytes = Vec::new();
+    GzDecoder::new(snapshot).read_to_end(&mut bytes)?;
+    Index::decode(bytes)
+        .map(Some)
+        .map_err(|e| Error::Deserialisation(format!("invalid bundled index: {}", e)))
+}
+
+/// How long ago the snapshot was taken.
+pub fn age() -> Option<Duration> {
+    let time = UNIX_EPOCH + Duration::from_secs(TIME.parse().ok()?);
+    SystemTime::now().duration_since(time).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use crate::index::Stamp;
+    use super::*;
+
+    #[test]
+    fn snapshots() {
+        assert!(read(&[]).unwrap().is_none());
+
+        let data = crate::schema::parse(fontpm_testing::google_fonts::INDEX.as_bytes()).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&Index::encode(&data, Stamp::default()).unwrap()).unwrap();
+        let index = read(&encoder.finish().unwrap()).unwrap().unwrap();
+        assert!(index.get_family("test-sans").unwrap().is_some());
+
+        assert!(read(b"not an index").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+use serde::Deserialize;
+use default_env::default_env;
+use fontpm_api::Error;
+use fontpm_api::host::toml;
+
+// GitHub API
+const COMMIT_DATA_URL: &str = default_env!("COMMIT", "https://api.github.com/repos/fontpm/data/branches/data");
+// Raw content™
+const FONT_INDEX_URL: &str = default_env!("FONT_INDEX_URL", "https://raw.githubusercontent.com/fontpm/data/data/google-fonts.json");
+// Files in the index are stored without a scheme (e.g. `fonts.gstatic.com/s/...`)
+const FILE_BASE_URL: &str = default_env!("FILE_BASE_URL", "https://");
+// The API browsers load Google Fonts from
+const CSS2_URL: &str = default_env!("CSS2_URL", "https://fonts.googleapis.com/css2");
+const RETRIES: u32 = 3;
+const RETRY_DELAY_MS: u64 = 500;
+
+/// Configuration from `[sources.google-fonts]`.
+/// Every option falls back to the value FontPM was built with.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct GoogleFontsConfig {
+    /// URL of the font index.
+    index_url: Option<String>,
+    /// URL of the GitHub API endpoint for the branch containing the index.
+    commit_url: Option<String>,
+    /// Prefix for the paths of font files in the index.
+    file_base_url: Option<String>,
+    /// Prefixes to try in order when downloading font files, instead of `file_base_url`.
+    mirrors: Vec<String>,
+    /// Token for the GitHub API, which is only used if the index server doesn't support conditional requests.
+    github_token: Option<String>,
+    /// Whether to resolve fonts through the CSS2 API instead of the index.
+    css2: bool,
+    /// URL of the CSS2 API.
+    css2_url: Option<String>,
+    /// How many times to retry a download that failed for a transient reason, per URL.
+    retries: Option<u32>,
+    /// The delay before the first retry in milliseconds, which is doubled for every retry after that.
+    retry_delay: Option<u64>
+}
+
+impl GoogleFontsConfig {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, Error> {
+        value.clone().try_into::<Self>()
+            .map_err(|v| Error::Deserialisation(format!("invalid google-fonts configuration: {}", v)))
+    }
+
+    pub fn index_url(&self) -> &str {
+        self.index_url.as_deref().unwrap_or(FONT_INDEX_URL)
+    }
+    pub fn commit_url(&self) -> &str {
+        self.commit_url.as_deref().unwrap_or(COMMIT_DATA_URL)
+    }
+    pub fn github_token(&self) -> Option<&str> {
+        self.github_token.as_deref()
+    }
+    pub fn css2(&self) -> bool {
+        self.css2
+    }
+    pub fn css2_url(&self, query: &str) -> String {
+        format!("{}?{}", self.css2_url.as_deref().unwrap_or(CSS2_URL), query)
+    }
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(RETRIES)
+    }
+    pub fn retry_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_delay.unwrap_or(RETRY_DELAY_MS))
+    }
+    /// The URLs `file` can be downloaded from, in the order they should be tried.
+    pub fn file_urls(&self, file: &str) -> Vec<String> {
+        if self.mirrors.is_empty() {
+            return vec![format!("{}{}", self.file_base_url.as_deref().unwrap_or(FILE_BASE_URL), file)]
+        }
+        self.mirrors.iter().map(|mirror| format!("{}{}", mirror, file)).collect()
+    }
+}
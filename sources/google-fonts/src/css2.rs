@@ -0,0 +1,183 @@
+//! Resolving families through the Google Fonts CSS2 API (`fonts.googleapis.com/css2`),
+//! which serves the same `@font-face` rules that browsers load.
+
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight};
+
+/// The API only serves WOFF2 files split by `unicode-range` to browsers it knows support them.
+pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// A single `@font-face` rule from a CSS2 API response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Css2Face {
+    /// The subset from the comment before the rule (e.g. `latin`), or `all` if there isn't one.
+    pub subset: String,
+    pub variant: DefinedFontVariantSpec,
+    pub url: String,
+    pub unicode_range: Option<String>
+}
+
+/// The query strings to request `variants` of `family` with.
+/// Variable fonts are requested separately as a `wght` range, since the API doesn't allow ranges to overlap with fixed weights.
+pub fn queries(family: &str, variants: &[DefinedFontVariantSpec], variable_range: (u32, u32)) -> Vec<String> {
+    let ital = |style: &DefinedFontStyle| match style {
+        DefinedFontStyle::Regular => 0,
+        DefinedFontStyle::Italic => 1
+    };
+    let mut fixed: Vec<(u32, u32)> = Vec::new();
+    let mut variable: Vec<u32> = Vec::new();
+    for variant in variants {
+        match variant.weight {
+            DefinedFontWeight::Fixed(weight) => fixed.push((ital(&variant.style), weight)),
+            DefinedFontWeight::Variable => variable.push(ital(&variant.style))
+        }
+    }
+    // the API rejects tuples that aren't sorted
+    fixed.sort();
+    fixed.dedup();
+    variable.sort();
+    variable.dedup();
+
+    let family = family.replace(' ', "+");
+    let mut queries = Vec::new();
+    if !fixed.is_empty() {
+        let tuples: Vec<String> = fixed.iter().map(|(ital, weight)| format!("{},{}", ital, weight)).collect();
+        queries.push(format!("family={}:ital,wght@{}&display=swap", family, tuples.join(";")));
+    }
+    if !variable.is_empty() {
+        let (min, max) = variable_range;
+        let tuples: Vec<String> = variable.iter().map(|ital| format!("{},{}..{}", ital, min, max)).collect();
+        queries.push(format!("family={}:ital,wght@{}&display=swap", family, tuples.join(";")));
+    }
+    queries
+}
+
+/// Parses the `@font-face` rules in a CSS2 API response.
+pub fn parse(css: &str) -> Result<Vec<Css2Face>, Error> {
+    let invalid = |message: &str| Error::Deserialisation(format!("invalid CSS2 API response: {}", message));
+
+    let mut faces = Vec::new();
+    let mut rest = css;
+    while let Some(start) = rest.find("@font-face") {
+        let before = rest[..start].trim_end();
+        let subset = before.strip_suffix("*/")
+            .and_then(|v| v.rfind("/*").map(|i| &v[i + 2..]))
+            .map(|v| v.trim().trim_matches(|c| c == '[' || c == ']').to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "all".to_string());
+
+        let body_start = rest[start..].find('{').ok_or_else(|| invalid("unterminated @font-face"))? + start + 1;
+        let body_end = rest[body_start..].find('}').ok_or_else(|| invalid("unterminated @font-face"))? + body_start;
+
+        let mut style = DefinedFontStyle::Regular;
+        let mut weight = DefinedFontWeight::REGULAR;
+        let mut url = None;
+        let mut unicode_range = None;
+        for declaration in rest[body_start..body_end].split(';') {
+            let Some((name, value)) = declaration.split_once(':') else {
+                continue
+            };
+            let value = value.trim();
+            match name.trim() {
+                "font-style" => style = value.parse()?,
+                "font-weight" => weight = match value.split_once(' ') {
+                    Some(_) => DefinedFontWeight::Variable,
+                    None => DefinedFontWeight::Fixed(value.parse().map_err(|_| invalid(&format!("invalid font-weight {}", value)))?)
+                },
+                "src" => url = value.split_once("url(")
+                    .and_then(|(_, v)| v.split_once(')'))
+                    .map(|(v, _)| v.trim_matches(|c| c == '\'' || c == '"').to_string()),
+                "unicode-range" => unicode_range = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        faces.push(Css2Face {
+            subset,
+            variant: DefinedFontVariantSpec { weight, style },
+            url: url.ok_or_else(|| invalid("@font-face without a url"))?,
+            unicode_range
+        });
+        rest = &rest[body_end + 1..];
+    }
+
+    Ok(faces)
+}
+
+/// A guess of the name of the family `id` (e.g. `Noto Sans` for `noto-sans`), for when it isn't in the index.
+pub fn family_name(id: &str) -> String {
+    id.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The version of a family from the path of one of its files (e.g. `/s/roboto/v30/...`).
+pub fn version_from_url(url: &str) -> Option<String> {
+    url.split('/')
+        .filter_map(|v| v.strip_prefix('v'))
+        .find(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit()))
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"/* cyrillic */
+@font-face {
+  font-family: 'Test Sans';
+  font-style: italic;
+  font-weight: 400;
+  font-display: swap;
+  src: url(https://fonts.gstatic.com/s/testsans/v3/a.woff2) format('woff2');
+  unicode-range: U+0400-045F;
+}
+/* latin */
+@font-face {
+  font-family: 'Test Sans';
+  font-style: normal;
+  font-weight: 100 900;
+  src: url(https://fonts.gstatic.com/s/testsans/v3/b.woff2) format('woff2');
+  unicode-range: U+0000-00FF, U+0131;
+}
+"#;
+
+    #[test]
+    fn parses_faces() {
+        let faces = parse(RESPONSE).unwrap();
+        assert_eq!(faces, vec![
+            Css2Face {
+                subset: "cyrillic".into(),
+                variant: DefinedFontVariantSpec { weight: DefinedFontWeight::REGULAR, style: DefinedFontStyle::Italic },
+                url: "https://fonts.gstatic.com/s/testsans/v3/a.woff2".into(),
+                unicode_range: Some("U+0400-045F".into())
+            },
+            Css2Face {
+                subset: "latin".into(),
+                variant: DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Regular },
+                url: "https://fonts.gstatic.com/s/testsans/v3/b.woff2".into(),
+                unicode_range: Some("U+0000-00FF, U+0131".into())
+            }
+        ]);
+        assert_eq!(version_from_url(&faces[0].url).as_deref(), Some("3"));
+        assert_eq!(family_name("test-sans"), "Test Sans");
+    }
+
+    #[test]
+    fn builds_sorted_queries() {
+        let variants = [
+            DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Regular },
+            DefinedFontVariantSpec { weight: DefinedFontWeight::REGULAR, style: DefinedFontStyle::Italic },
+            DefinedFontVariantSpec::REGULAR,
+            DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Italic }
+        ];
+        assert_eq!(queries("Test Sans", &variants, (400, 700)), vec![
+            "family=Test+Sans:ital,wght@0,400;0,700;1,400&display=swap".to_string(),
+            "family=Test+Sans:ital,wght@1,400..700&display=swap".to_string()
+        ]);
+    }
+}
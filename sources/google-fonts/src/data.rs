@@ -1,7 +1,10 @@
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use fontpm_api::Error;
-use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, DefinedFontWeight, FontDescription as FpmFontDescription};
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription as FpmFontDescription};
+use fontpm_api::integrity::FileIntegrity;
+use fontpm_api::source::{FontUpdate, IndexChanges};
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct FontDescription {
@@ -12,16 +15,107 @@ pub struct FontDescription {
     #[serde(alias = "lastModified")]
     pub last_modified: u64,
     pub files: HashMap<String, String>,
-    pub variants: Vec<String>
+    pub variants: Vec<String>,
+    /// The variable font files, by style (`regular` or `italic`).
+    /// These come from the `variable` files of version 2 indices; version 1 indices (like the upstream one) don't have them.
+    #[serde(default)]
+    pub variable_files: HashMap<String, String>,
+    /// The files of each subset (e.g. `latin`), keyed by subset and then by variant like `files`
+    /// (with `variable` and `variableitalic` for the variable fonts).
+    #[serde(default, alias = "subsetFiles")]
+    pub subset_files: HashMap<String, HashMap<String, String>>,
+    /// The paths of the license documents (e.g. `OFL.txt`), by file name.
+    #[serde(default, alias = "licenseFiles")]
+    pub license_files: HashMap<String, String>,
+    /// What the files of this family are expected to be, by path.
+    /// Older indices don't have these, and files without them aren't checked.
+    #[serde(default)]
+    pub integrity: HashMap<String, FileDigest>,
+    /// The category of the family (e.g. `sans-serif`). Only in version 2 indices and later, which is also true of the fields below.
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub designers: Vec<String>,
+    /// The axes of the variable fonts.
+    #[serde(default)]
+    pub axes: Vec<FontAxis>,
+    /// The ID of the license (e.g. `ofl`).
+    #[serde(default)]
+    pub license: Option<String>
+}
+
+/// A variation axis of a variable font, like `wght` from 100 to 900.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct FontAxis {
+    pub tag: String,
+    pub min: f32,
+    pub max: f32
+}
+
+/// The expected size and SHA-256 digest (as hex) of a file in the index.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct FileDigest {
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>
+}
+
+impl FontDescription {
+    /// Every variant of this family: the fixed weights in index order, followed by the variable fonts.
+    pub fn all_variants(&self) -> Result<Vec<DefinedFontVariantSpec>, Error> {
+        let mut variants: Vec<DefinedFontVariantSpec> = self.variants.iter()
+            .map(description::string_to_variant)
+            .collect::<Result<_, _>>()?;
+
+        let mut variable: Vec<DefinedFontVariantSpec> = self.variable_files.keys()
+            .map(|style| Ok(DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::from_str(style)? }))
+            .collect::<Result<_, Error>>()?;
+        variable.sort();
+        variants.append(&mut variable);
+
+        Ok(variants)
+    }
+
+    /// The range of the `wght` axis, if the index has it.
+    pub fn weight_range(&self) -> Option<(u32, u32)> {
+        self.axes.iter()
+            .find(|v| v.tag == "wght")
+            .map(|v| (v.min as u32, v.max as u32))
+    }
+
+    /// What the file at `path` is expected to be, which is nothing in particular if the index doesn't say.
+    pub fn integrity_for(&self, path: &str) -> FileIntegrity {
+        self.integrity.get(path)
+            .map_or_else(FileIntegrity::default, |v| FileIntegrity::new(v.size, v.sha256.clone()))
+    }
+
+    /// The total size of the files of every variant, if the index has the size of all of them.
+    pub fn size(&self) -> Option<u64> {
+        self.all_variants().ok()?.iter()
+            .map(|variant| self.integrity.get(self.file_for(variant)?)?.size)
+            .sum()
+    }
+
+    /// The path of the file for `variant` in `subset`, if the family has it.
+    pub fn subset_file_for(&self, variant: &DefinedFontVariantSpec, subset: &str) -> Option<&String> {
+        self.subset_files.get(subset)?.get(&description::variant_to_string(variant))
+    }
+
+    /// The path of the file for `variant`, if the family has it.
+    pub fn file_for(&self, variant: &DefinedFontVariantSpec) -> Option<&String> {
+        match variant.weight {
+            DefinedFontWeight::Variable => self.variable_files.get(variant.style.as_ref()),
+            DefinedFontWeight::Fixed(_) => self.files.get(&description::variant_to_string(variant))
+        }
+    }
 }
 
 impl TryFrom<FontDescription> for DefinedFontInstallSpec {
     type Error = Error;
 
     fn try_from(value: FontDescription) -> Result<Self, Self::Error> {
-        let variants: Result<Vec<DefinedFontVariantSpec>, Error> =
-            value.variants.into_iter().map(|v| description::string_to_variant(v)).collect();
-        let variants = variants?;
+        let variants = value.all_variants()?;
 
         Ok(DefinedFontInstallSpec::new(value.id, variants))
     }
@@ -35,7 +129,10 @@ impl From<FontDescription> for FpmFontDescription {
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Data {
     families: HashMap<String, FontDescription>,
-    tags: HashMap<String, Vec<String>>
+    tags: HashMap<String, Vec<String>>,
+    /// The characters in each subset, as CSS `unicode-range` values.
+    #[serde(default)]
+    subsets: HashMap<String, String>
 }
 
 const REGULAR_WEIGHT: DefinedFontWeight =  DefinedFontWeight::Fixed(400);
@@ -92,7 +189,7 @@ pub mod description {
         }
         let weight = match variant.weight {
             DefinedFontWeight::Fixed(weight) => weight.to_string(),
-            DefinedFontWeight::Variable => "wght".to_string() // isn't actually used so...
+            DefinedFontWeight::Variable => "variable".to_string()
         };
         let style = match variant.style {
             DefinedFontStyle::Regular => "",
@@ -103,6 +200,20 @@ pub mod description {
 }
 
 impl Data {
+    pub(crate) fn new(families: HashMap<String, FontDescription>, tags: HashMap<String, Vec<String>>, subsets: HashMap<String, String>) -> Self {
+        Data { families, tags, subsets }
+    }
+
+    pub(crate) fn families(&self) -> &HashMap<String, FontDescription> {
+        &self.families
+    }
+    pub(crate) fn tags(&self) -> &HashMap<String, Vec<String>> {
+        &self.tags
+    }
+    pub(crate) fn subsets(&self) -> &HashMap<String, String> {
+        &self.subsets
+    }
+
     pub fn get_family(&self, id: &String) -> Option<FontDescription> {
         self.families.get(id).map(|v| v.clone())
     }
@@ -146,4 +257,36 @@ impl Data {
 
         Vec::from_iter(set.into_iter())
     }
-}
\ No newline at end of file
+
+    /// The families added, removed and updated since `previous`, sorted by ID.
+    pub fn changes_since(&self, previous: &Data) -> Result<IndexChanges, Error> {
+        let mut changes = IndexChanges::default();
+        for family in self.families.values() {
+            let old = match previous.families.get(&family.id) {
+                Some(v) => v,
+                None => {
+                    changes.added.push(family.clone().into());
+                    continue
+                }
+            };
+
+            let old_variants = old.all_variants()?;
+            let added_variants: Vec<DefinedFontVariantSpec> = family.all_variants()?.into_iter()
+                .filter(|v| !old_variants.contains(v))
+                .collect();
+            let previous_version = (old.version != family.version).then(|| old.version.to_string());
+            if previous_version.is_some() || !added_variants.is_empty() {
+                changes.updated.push(FontUpdate { font: family.clone().into(), previous_version, added_variants });
+            }
+        }
+        changes.removed = previous.families.values()
+            .filter(|v| !self.families.contains_key(&v.id))
+            .map(|v| v.clone().into())
+            .collect();
+
+        changes.added.sort_by(|a, b| a.id.cmp(&b.id));
+        changes.removed.sort_by(|a, b| a.id.cmp(&b.id));
+        changes.updated.sort_by(|a, b| a.font.id.cmp(&b.font.id));
+        Ok(changes)
+    }
+}
@@ -0,0 +1,263 @@
+//! A binary form of the index, written at refresh time, so that looking up a family doesn't mean parsing all of `data.json`.
+//!
+//! The layout (with little-endian integers) is:
+//! - [`MAGIC`], followed by the format version as a `u32`
+//! - the [`Stamp`] of the `data.json` the index was built from: its size and modification time as `u64`s
+//! - the number of families as a `u32`, followed by one entry per family, sorted by ID:
+//!   the length of the ID as a `u32`, the ID, and the offset and length of its description as `u32`s
+//! - the length of the tag table as a `u32`, followed by the tag table
+//! - the length of the subset table as a `u32`, followed by the subset table
+//! - the family descriptions, which are only decoded when they're looked up
+//!
+//! Offsets are relative to the start of the file, so the file can be used as-is (e.g. when memory-mapped).
+//! The tag table, subset table and descriptions are encoded with bincode.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use fontpm_api::Error;
+use crate::data::{Data, FontDescription};
+
+pub const MAGIC: &[u8; 8] = b"FPMGFIDX";
+/// Increased whenever the layout or [`FontDescription`] changes.
+pub const VERSION: u32 = 6;
+
+/// Identifies the `data.json` an index was built from, so that an index isn't used once `data.json` has changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stamp {
+    pub len: u64,
+    /// In nanoseconds since the Unix epoch.
+    pub modified: u64
+}
+
+impl Stamp {
+    pub fn of(path: &Path) -> Result<Self, Error> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata.modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |v| v.as_nanos() as u64);
+        Ok(Stamp { len: metadata.len(), modified })
+    }
+}
+
+pub struct Index {
+    bytes: Vec<u8>,
+    stamp: Stamp,
+    families: Vec<(String, Range<usize>)>,
+    tags: HashMap<String, Vec<String>>,
+    subsets: HashMap<String, String>
+}
+
+fn invalid(message: impl ToString) -> Error {
+    Error::Deserialisation(format!("invalid index: {}", message.to_string()))
+}
+
+fn u32_of(value: usize) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| Error::Generic("the index is too large".into()))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(len)
+            .filter(|v| *v <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+    fn u32(&mut self) -> Result<u32, Error> {
+        let mut value = [0u8; 4];
+        value.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(value))
+    }
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut value = [0u8; 8];
+        value.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(value))
+    }
+}
+
+impl Index {
+    pub fn encode(data: &Data, stamp: Stamp) -> Result<Vec<u8>, Error> {
+        let encode_error = |e: bincode::Error| Error::Generic(format!("could not encode index: {}", e));
+
+        let mut families: Vec<&FontDescription> = data.families().values().collect();
+        families.sort_by(|a, b| a.id.cmp(&b.id));
+        let descriptions = families.iter()
+            .map(bincode::serialize)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(encode_error)?;
+        let tags = bincode::serialize(data.tags()).map_err(encode_error)?;
+        let subsets = bincode::serialize(data.subsets()).map_err(encode_error)?;
+
+        let directory_len: usize = families.iter().map(|v| 12 + v.id.len()).sum();
+        let mut offset = MAGIC.len() + 4 + 16 + 4 + directory_len + 4 + tags.len() + 4 + subsets.len();
+
+        let mut bytes = Vec::with_capacity(offset + descriptions.iter().map(Vec::len).sum::<usize>());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&stamp.len.to_le_bytes());
+        bytes.extend_from_slice(&stamp.modified.to_le_bytes());
+        bytes.extend_from_slice(&u32_of(families.len())?.to_le_bytes());
+        for (family, description) in families.iter().zip(&descriptions) {
+            bytes.extend_from_slice(&u32_of(family.id.len())?.to_le_bytes());
+            bytes.extend_from_slice(family.id.as_bytes());
+            bytes.extend_from_slice(&u32_of(offset)?.to_le_bytes());
+            bytes.extend_from_slice(&u32_of(description.len())?.to_le_bytes());
+            offset += description.len();
+        }
+        bytes.extend_from_slice(&u32_of(tags.len())?.to_le_bytes());
+        bytes.extend_from_slice(&tags);
+        bytes.extend_from_slice(&u32_of(subsets.len())?.to_le_bytes());
+        bytes.extend_from_slice(&subsets);
+        for description in descriptions {
+            bytes.extend_from_slice(&description);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Reads the directory and tag table of an encoded index. Descriptions are decoded on lookup.
+    pub fn decode(bytes: Vec<u8>) -> Result<Self, Error> {
+        let mut reader = Reader { bytes: &bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not an index file"))
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported version {} (expected {})", version, VERSION)))
+        }
+        let stamp = Stamp { len: reader.u64()?, modified: reader.u64()? };
+
+        let count = reader.u32()? as usize;
+        let mut families = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            let id = std::str::from_utf8(reader.take(len)?).map_err(invalid)?.to_string();
+            let start = reader.u32()? as usize;
+            let len = reader.u32()? as usize;
+            if start.checked_add(len).is_none_or(|end| end > bytes.len()) {
+                return Err(invalid(format!("description of {} is out of bounds", id)))
+            }
+            families.push((id, start..start + len));
+        }
+
+        let len = reader.u32()? as usize;
+        let tags = bincode::deserialize(reader.take(len)?).map_err(invalid)?;
+
+        let len = reader.u32()? as usize;
+        let subsets = bincode::deserialize(reader.take(len)?).map_err(invalid)?;
+
+        Ok(Index { bytes, stamp, families, tags, subsets })
+    }
+
+    /// The stamp of the `data.json` this index was built from.
+    pub fn stamp(&self) -> Stamp {
+        self.stamp
+    }
+
+    fn decode_family(&self, range: &Range<usize>) -> Result<FontDescription, Error> {
+        bincode::deserialize(&self.bytes[range.clone()]).map_err(invalid)
+    }
+
+    pub fn get_family(&self, id: &str) -> Result<Option<FontDescription>, Error> {
+        match self.families.binary_search_by(|(v, _)| v.as_str().cmp(id)) {
+            Ok(i) => self.decode_family(&self.families[i].1).map(Some),
+            Err(_) => Ok(None)
+        }
+    }
+
+    /// The characters in `subset`, as a CSS `unicode-range` value.
+    pub fn unicode_range(&self, subset: &str) -> Option<&String> {
+        self.subsets.get(subset)
+    }
+
+    /// Every family, sorted by ID.
+    pub fn families(&self) -> impl Iterator<Item = Result<FontDescription, Error>> + '_ {
+        self.families.iter().map(|(_, range)| self.decode_family(range))
+    }
+
+    /// Finds all families that have every one of `tags` (case-insensitively), sorted by ID.
+    pub fn search_by_tags(&self, tags: &[String]) -> Result<Vec<FontDescription>, Error> {
+        let mut ids: Option<Vec<&String>> = None;
+        for tag in tags {
+            let tagged = self.tags.get(&tag.to_lowercase()).map_or(&[][..], Vec::as_slice);
+            ids = Some(match ids {
+                None => tagged.iter().collect(),
+                Some(ids) => ids.into_iter().filter(|v| tagged.contains(v)).collect()
+            });
+        }
+
+        let mut ids = ids.unwrap_or_default();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter()
+            .filter_map(|id| self.get_family(id).transpose())
+            .collect()
+    }
+
+    /// Finds all families whose ID or display name contains `query` (case-insensitively), sorted by ID.
+    pub fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let query = query.to_lowercase();
+        let mut found = Vec::new();
+        for family in self.families() {
+            let family = family?;
+            if family.id.contains(&query)
+                || family.display_name.to_lowercase().contains(&query)
+                || family.designers.iter().any(|v| v.to_lowercase().contains(&query)) {
+                found.push(family);
+            }
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Data {
+        serde_json::from_str(fontpm_testing::google_fonts::INDEX).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let stamp = Stamp { len: 1234, modified: 5678 };
+        let index = Index::decode(Index::encode(&data(), stamp).unwrap()).unwrap();
+        assert_eq!(index.stamp(), stamp);
+
+        let family = index.get_family("test-sans").unwrap().unwrap();
+        assert_eq!(family.display_name, "Test Sans");
+        assert_eq!(family.variable_files.len(), 2);
+        assert!(index.get_family("no-such-font").unwrap().is_none());
+        assert_eq!(index.unicode_range("latin").map(String::as_str), Some("U+0000-00FF"));
+
+        let ids: Vec<_> = index.search("TEST").unwrap().into_iter().map(|v| v.id).collect();
+        assert_eq!(ids, vec!["test-mono", "test-sans"]);
+
+        let tagged = |tags: &[&str]| -> Vec<String> {
+            let tags: Vec<String> = tags.iter().map(ToString::to_string).collect();
+            index.search_by_tags(&tags).unwrap().into_iter().map(|v| v.id).collect()
+        };
+        assert_eq!(tagged(&["Monospace"]), vec!["test-mono"]);
+        assert!(tagged(&["monospace", "sans-serif"]).is_empty());
+        assert!(tagged(&["no-such-tag"]).is_empty());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let mut bytes = Index::encode(&data(), Stamp::default()).unwrap();
+        assert!(Index::decode(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(Index::decode(bytes[..20].to_vec()).is_err());
+        assert!(Index::decode(b"{\"families\": {}}".to_vec()).is_err());
+
+        bytes[MAGIC.len()] = 0;
+        assert!(Index::decode(bytes).is_err());
+    }
+}
@@ -1,38 +1,76 @@
 mod github;
 mod data;
+mod config;
+mod validators;
+mod index;
+mod css2;
+mod schema;
+mod retry;
+#[cfg(feature = "bundled-index")]
+mod bundled;
 
 use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
+use std::fs::{create_dir_all, remove_file, File, OpenOptions};
 use std::path::{Path, PathBuf};
-use default_env::default_env;
-use fontpm_api::{FpmHost, Source, trace};
+use std::sync::{Arc, RwLock};
+use fontpm_api::{FpmHost, Source, trace, warning};
 use fontpm_api::async_trait::async_trait;
-use fontpm_api::host::EmptyFpmHost;
-use fontpm_api::source::{RefreshOutput};
+use fontpm_api::host::{EmptyFpmHost, DEFAULT_MAX_CONCURRENT_DOWNLOADS};
+use fontpm_api::registry::SourceFactory;
+use fontpm_api::source::{IndexChanges, RefreshOutput, TaggedFont};
 use fontpm_api::Error;
-use std::io::{Error as IOError, ErrorKind as IOErrorKind, Read, Write};
-use reqwest::{Client, ClientBuilder};
+use std::io::{BufReader, Error as IOError, ErrorKind as IOErrorKind, Read, Write};
+use reqwest::{Client, ClientBuilder, StatusCode};
+use reqwest::header::{CONTENT_RANGE, IF_RANGE, RANGE, USER_AGENT};
 use serde::{Serialize};
 use serde::de::DeserializeOwned;
 use sha2::{Sha256, Digest};
-use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontInstallSpec, FontDescription as FpmFontDescription};
-use fontpm_api::util::create_parent;
-use crate::data::Data;
+use tokio::sync::Semaphore;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontInstallSpec, FontStyle, FontSubset, FontVariantSpec, FontWeight, FontDescription as FpmFontDescription};
+use fontpm_api::integrity::FileIntegrity;
+use fontpm_api::util::{create_parent, nice_list, plural_s};
+pub use crate::data::{Data, FontDescription};
+use crate::css2::Css2Face;
+use crate::index::{Index, Stamp};
 use crate::data::description::variant_to_string;
+use crate::config::GoogleFontsConfig;
 use crate::github::GithubBranchData;
+use crate::validators::{IndexValidators, PartValidators};
+use crate::retry::Failure;
 
 pub struct GoogleFontsSource<'host> {
     host: &'host dyn FpmHost,
-    client: Option<Client>
+    client: Option<Client>,
+    config: GoogleFontsConfig,
+    /// The index, once it has been loaded.
+    index: RwLock<Option<Arc<Index>>>,
+    /// The parsed CSS2 API responses, by URL.
+    css2_responses: RwLock<HashMap<String, Arc<Vec<Css2Face>>>>,
+    /// Limits how many files are downloaded at the same time, across every font.
+    downloads: Semaphore
 }
 
-// GitHub API
-const COMMIT_DATA_URL: &str = default_env!("COMMIT", "https://api.github.com/repos/fontpm/data/branches/data");
-// Raw content™
-const FONT_INDEX_URL: &str = default_env!("FONT_INDEX_URL", "https://raw.githubusercontent.com/fontpm/data/data/google-fonts.json");
+/// A file to download with [`GoogleFontsSource::download_all`].
+struct Download {
+    remote_file: String,
+    urls: Vec<String>,
+    integrity: FileIntegrity,
+    dir: PathBuf
+}
 
 const COMMIT_FILE: &str = "commit.sha";
 const DATA_FILE: &str = "data.json";
+const VALIDATORS_FILE: &str = "data.validators.json";
+const INDEX_FILE: &str = "data.bin";
+/// The index that was replaced by the last refresh, kept to list what changed.
+const PREVIOUS_DATA_FILE: &str = "data.previous.json";
+
+fn create_source(host: &dyn FpmHost) -> Box<dyn Source<'_> + '_> {
+    let mut source = GoogleFontsSource::new();
+    source.set_host(host);
+    Box::new(source)
+}
+fontpm_api::register_source!(SourceFactory::new(GoogleFontsSource::ID, GoogleFontsSource::NAME, true, create_source));
 
 impl<'host> GoogleFontsSource<'host> {
     pub const ID: &'host str = "google-fonts";
@@ -41,7 +79,11 @@ impl<'host> GoogleFontsSource<'host> {
     pub fn new() -> Self {
         return GoogleFontsSource {
             host: &EmptyFpmHost::EMPTY_HOST,
-            client: None
+            client: None,
+            config: GoogleFontsConfig::default(),
+            index: RwLock::new(None),
+            css2_responses: RwLock::new(HashMap::new()),
+            downloads: Semaphore::new(DEFAULT_MAX_CONCURRENT_DOWNLOADS)
         };
     }
 
@@ -92,7 +134,11 @@ impl<'host> GoogleFontsSource<'host> {
     }
 
     async fn latest_commit(&self) -> Result<String, Error> {
-        let response = self.client().get(COMMIT_DATA_URL).send().await?;
+        let mut request = self.client().get(self.config.commit_url());
+        if let Some(token) = self.config.github_token() {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?.error_for_status()?;
         #[cfg(debug_assertions)]
         let data = {
             let text = response.text().await?;
@@ -105,10 +151,168 @@ impl<'host> GoogleFontsSource<'host> {
         Ok(data.commit.sha)
     }
 
-    async fn get_data(&self) -> Result<Data, reqwest::Error> {
-        let response = self.client().get(FONT_INDEX_URL).send().await?;
-        let data = response.json::<Data>().await?;
-        Ok(data)
+    /// Downloads the index, unless `validators` show that it hasn't changed (in which case this returns `None`).
+    async fn get_data(&self, validators: Option<&IndexValidators>) -> Result<Option<(Data, IndexValidators)>, Error> {
+        let mut request = self.client().get(self.config.index_url());
+        if let Some(validators) = validators {
+            request = validators.apply(request);
+        }
+        let response = request.send().await?.error_for_status()?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None)
+        }
+
+        let validators = IndexValidators::from_response(&response);
+        let data = schema::parse(&response.bytes().await?)?;
+        Ok(Some((data, validators)))
+    }
+
+    /// Downloads a file into `part` from the first of `urls` that has it and matches `integrity`.
+    async fn download_file(&self, urls: Vec<String>, integrity: &FileIntegrity, part: &Path) -> Result<(), Error> {
+        let mut last_error = None;
+        for url in urls {
+            match self.download_with_retries(&url, integrity, part).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    warning!("[{}] Could not download {}: {}", Self::ID, url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::Generic("no mirrors are configured".into())))
+    }
+
+    /// Downloads `url` into `part`, trying again with an increasing delay after transient errors.
+    async fn download_with_retries(&self, url: &str, integrity: &FileIntegrity, part: &Path) -> Result<(), Error> {
+        let mut attempt = 0;
+        loop {
+            let failure = match self.try_download(url, integrity, part).await {
+                Ok(resumed) => match std::fs::read(part).map_err(Error::from).and_then(|v| integrity.verify_bytes(url, &v)) {
+                    Ok(()) => return Ok(()),
+                    Err(e) => {
+                        remove_part(part);
+                        // the part that was resumed might not have been the start of this file, so downloading all of it could still work
+                        Failure { error: e, transient: resumed }
+                    }
+                },
+                Err(failure) => failure
+            };
+
+            if !failure.transient || attempt >= self.config.retries() {
+                return Err(failure.error)
+            }
+            attempt += 1;
+            let delay = retry::delay(self.config.retry_delay(), attempt);
+            trace!("[{}] Retrying {} in {:?} ({}/{}): {}", Self::ID, url, delay, attempt, self.config.retries(), failure.error);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Makes one attempt at downloading `url` into `part`, continuing from the end of `part` if it already exists and the server supports it.
+    /// Returns whether the download was resumed. If the connection breaks halfway, whatever was received is kept for the next attempt.
+    ///
+    /// A part is only resumed if it came from the same URL, and either the server can check that the file hasn't changed since (with `If-Range`),
+    /// or `integrity` has a digest that the whole file will be checked against.
+    async fn try_download(&self, url: &str, integrity: &FileIntegrity, part: &Path) -> Result<bool, Failure> {
+        let _permit = self.downloads.acquire().await.expect("the download semaphore is never closed");
+        let mut offset = part.metadata().map_or(0, |v| v.len());
+        let mut request = self.client().get(url);
+        if offset > 0 {
+            let saved = PartValidators::read(part).filter(|v| v.url == url);
+            let if_range = saved.as_ref().and_then(PartValidators::if_range);
+            if saved.is_some() && (if_range.is_some() || integrity.sha256.is_some()) {
+                request = request.header(RANGE, format!("bytes={}-", offset));
+                if let Some(if_range) = if_range {
+                    request = request.header(IF_RANGE, if_range);
+                }
+            } else {
+                trace!("[{}] Downloading {} from the start, as the partial download can't be checked", Self::ID, url);
+                remove_part(part);
+                offset = 0;
+            }
+        }
+
+        let mut response = request.send().await?;
+        let resumed = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let range = response.headers().get(CONTENT_RANGE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+                if !range.starts_with(&format!("bytes {}-", offset)) {
+                    remove_part(part);
+                    return Err(Failure::transient(Error::Generic(format!("the server sent the unexpected range '{}'", range))))
+                }
+                trace!("[{}] Resuming {} from byte {}", Self::ID, url, offset);
+                true
+            },
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                // the part is at least as long as the whole file, so it can't be the start of it
+                remove_part(part);
+                return Err(Failure::transient(Error::Generic(format!("{} is shorter than the partial download", url))))
+            },
+            _ => {
+                response = response.error_for_status()?;
+                false
+            }
+        };
+
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(part)
+        } else {
+            PartValidators::from_response(url, &response).write(part)
+                .and_then(|_| File::create(part))
+        }.map_err(Failure::permanent)?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).map_err(Failure::permanent)?;
+        }
+        Ok(resumed)
+    }
+
+    /// Downloads `remote_file` from `urls` into `dir`, unless it has already been downloaded.
+    /// Both downloads and cached files are checked against `integrity`, and cached files that don't match are downloaded again.
+    async fn download_cached(&self, remote_file: &str, urls: Vec<String>, integrity: &FileIntegrity, dir: &Path) -> Result<PathBuf, Error> {
+        let extension = PathBuf::from(remote_file).extension().map_or(String::new(), |v| ".".to_string() + v.to_str().unwrap());
+        let url_hash = Sha256::new()
+            .chain_update(remote_file)
+            .finalize();
+        let url_hash = format!("{:x}", url_hash);
+        let path = dir.join(format!("{}{}", url_hash, extension));
+
+        if path.exists() {
+            match integrity.verify(&path) {
+                Ok(()) => return Ok(path),
+                Err(e) => {
+                    warning!("[{}] Downloading {} again: {}", Self::ID, remote_file, e);
+                }
+            }
+        }
+        path.parent().map(create_dir_all);
+
+        // the file is only moved into place once it's complete, so an interrupted download never looks cached,
+        // and the part is kept after transient errors so that the next attempt can resume it
+        let part = dir.join(format!("{}{}.part", url_hash, extension));
+        self.download_file(urls, integrity, &part).await?;
+        std::fs::rename(&part, &path)?;
+        let _ = remove_file(PartValidators::path_for(&part));
+        Ok(path)
+    }
+
+    /// Downloads every file in `downloads` with [`GoogleFontsSource::download_cached`], at the same time as far as the download limit allows.
+    /// The paths are in the same order as `downloads`, and if some downloads fail, the error of the first of them is returned.
+    async fn download_all(&self, downloads: Vec<Download>) -> Result<Vec<PathBuf>, Error> {
+        futures::future::join_all(downloads.iter().map(|v| self.download_cached(&v.remote_file, v.urls.clone(), &v.integrity, &v.dir)))
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    /// A [`Download`] of `remote_file` from the configured mirrors into `dir`, checked against what the index expects of `font`.
+    fn download_of(&self, font: &FontDescription, remote_file: &str, dir: PathBuf) -> Download {
+        Download {
+            remote_file: remote_file.to_string(),
+            urls: self.config.file_urls(remote_file),
+            integrity: font.integrity_for(remote_file),
+            dir
+        }
     }
 
     fn cache_write_str<S, V>(&self, file: S, value: V) -> Result<(), Error> where S: AsRef<Path>, V: Into<String> {
@@ -137,7 +341,7 @@ impl<'host> GoogleFontsSource<'host> {
             return Err(Error::IO(IOErrorKind::NotFound.into()))
         }
 
-        let file = File::open(path)?;
+        let file = BufReader::new(File::open(path)?);
         let result = serde_json::de::from_reader::<_, T>(file)
             .map_err(|v| Error::Deserialisation(format!("{}", v)));
 
@@ -150,21 +354,253 @@ impl<'host> GoogleFontsSource<'host> {
             return Err(Error::IO(IOError::new(IOErrorKind::NotFound, "Data file does not exist")))
         }
 
-        self.cache_read_deserialise(data_file)
+        schema::parse(&std::fs::read(data_file)?)
+    }
+
+    /// Replaces the cached index with `data`, keeping the current one as the previous index.
+    fn replace_data(&self, data: &Data) -> Result<(), Error> {
+        let data_file = self.cache_file(DATA_FILE);
+        if data_file.exists() {
+            std::fs::rename(&data_file, self.cache_file(PREVIOUS_DATA_FILE))?;
+        }
+        self.cache_write_serialise(DATA_FILE, data)?;
+        self.write_index(data)?;
+        Ok(())
+    }
+
+    /// Writes the binary index for `data` (which has to be what `data.json` contains), and uses it from now on.
+    fn write_index(&self, data: &Data) -> Result<Arc<Index>, Error> {
+        let bytes = Index::encode(data, Stamp::of(&self.cache_file(DATA_FILE))?)?;
+        let path = self.cache_file(INDEX_FILE);
+        create_parent(&path)?;
+        std::fs::write(path, &bytes)?;
+
+        let index = Arc::new(Index::decode(bytes)?);
+        *self.index.write().unwrap() = Some(index.clone());
+        Ok(index)
+    }
+
+    /// The index, which is loaded the first time it's needed.
+    /// If the binary index is missing, was written by another version or doesn't match `data.json`, it is rebuilt from `data.json`.
+    fn index(&self) -> Result<Arc<Index>, Error> {
+        if let Some(index) = self.index.read().unwrap().as_ref() {
+            return Ok(index.clone())
+        }
+
+        #[cfg(feature = "bundled-index")]
+        if !self.cache_file(DATA_FILE).exists() {
+            if let Some(index) = bundled::index()? {
+                let age = bundled::age().map_or(String::new(), |v| format!(" from {} days ago", v.as_secs() / (24 * 60 * 60)));
+                warning!("[{}] Using the index bundled with FontPM{} - run `fontpm refresh` to get the latest fonts", Self::ID, age);
+                // kept in memory only, so that refreshing isn't skipped
+                let index = Arc::new(index);
+                *self.index.write().unwrap() = Some(index.clone());
+                return Ok(index)
+            }
+        }
+
+        let path = self.cache_file(INDEX_FILE);
+        let data_file = self.cache_file(DATA_FILE);
+        if path.exists() && data_file.exists() {
+            match Index::decode(std::fs::read(&path)?) {
+                Ok(index) if index.stamp() == Stamp::of(&data_file)? => {
+                    let index = Arc::new(index);
+                    *self.index.write().unwrap() = Some(index.clone());
+                    return Ok(index)
+                },
+                Ok(_) => {
+                    trace!("[{}] Rebuilding {}: {} has changed", Self::ID, INDEX_FILE, DATA_FILE);
+                },
+                Err(e) => {
+                    trace!("[{}] Rebuilding {}: {}", Self::ID, INDEX_FILE, e);
+                }
+            }
+        }
+
+        self.write_index(&self.read_data()?)
     }
+
+    /// The family `id` from the index, if there is an index and it has the family.
+    fn indexed_family(&self, id: &str) -> Option<FontDescription> {
+        self.index().ok()?.get_family(id).ok()?
+    }
+
+    /// Requests `variants` of the family `id` from the CSS2 API.
+    async fn css2_faces(&self, id: &str, variants: &[DefinedFontVariantSpec]) -> Result<Vec<Css2Face>, Error> {
+        let family = self.indexed_family(id);
+        let name = family.as_ref().map_or_else(|| css2::family_name(id), |v| v.display_name.clone());
+        // variable fonts are requested across the weight axis from version 2 indices, or otherwise across the fixed weights,
+        // which the API accepts even if the font supports more
+        let variable_range = family.as_ref()
+            .and_then(|family| {
+                if let Some(range) = family.weight_range() {
+                    return Some(range)
+                }
+                let weights: Vec<u32> = family.all_variants().ok()?.into_iter()
+                    .filter_map(|v| match v.weight {
+                        DefinedFontWeight::Fixed(weight) => Some(weight),
+                        DefinedFontWeight::Variable => None
+                    })
+                    .collect();
+                Some((*weights.iter().min()?, *weights.iter().max()?))
+            })
+            .unwrap_or((100, 900));
+
+        let mut faces = Vec::new();
+        for query in css2::queries(&name, variants, variable_range) {
+            let url = self.config.css2_url(&query);
+            let cached = self.css2_responses.read().unwrap().get(&url).cloned();
+            let response = match cached {
+                Some(response) => response,
+                None => {
+                    let response = self.client().get(&url).header(USER_AGENT, css2::USER_AGENT).send().await?;
+                    if response.status() == StatusCode::BAD_REQUEST {
+                        let hint = if family.is_none() {
+                            " (without the index, all weights are requested as a variable font - run `fontpm refresh` to request the weights it has)"
+                        } else {
+                            ""
+                        };
+                        return Err(Error::Generic(format!("the CSS2 API does not have {} in the requested styles{}", name, hint)))
+                    }
+                    let parsed = Arc::new(css2::parse(&response.error_for_status()?.text().await?)?);
+                    self.css2_responses.write().unwrap().insert(url, parsed.clone());
+                    parsed
+                }
+            };
+            faces.extend(response.iter().cloned());
+        }
+        Ok(faces)
+    }
+
+    /// Like [`Source::resolve_font`], but with the files the CSS2 API serves, as a subset per `unicode-range`.
+    async fn resolve_css2(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FpmFontDescription), Error> {
+        let family = self.indexed_family(&spec.id);
+        let mut variants = match &family {
+            Some(family) => family.all_variants()?,
+            // without the index, only the weights that were asked for are known
+            None => {
+                let mut variants = Vec::new();
+                for requested in &spec.styles {
+                    let weight = match requested.weight {
+                        FontWeight::Defined(weight) => weight,
+                        // the whole weight axis, which the API only has for variable fonts
+                        FontWeight::All => DefinedFontWeight::Variable,
+                        FontWeight::AllFixed => return Err(Error::Generic(format!(
+                            "the weights of {} aren't known until the index is refreshed - run `fontpm refresh` or ask for specific weights", spec.id
+                        )))
+                    };
+                    let style = match requested.style {
+                        FontStyle::Defined(style) => style,
+                        FontStyle::All => DefinedFontStyle::Regular
+                    };
+                    variants.push(DefinedFontVariantSpec { weight, style });
+                }
+                variants
+            }
+        };
+        variants.retain(|available| {
+            let available = FontVariantSpec::from(*available);
+            spec.styles.iter().any(|requested| available.is_covered_by(requested))
+        });
+        variants.sort();
+        variants.dedup();
+
+        let faces = self.css2_faces(&spec.id, &variants).await?;
+        let mut subsets: Vec<FontSubset> = Vec::new();
+        for face in &faces {
+            let requested = spec.subsets.is_empty() || spec.subsets.contains(&face.subset);
+            if requested && !subsets.iter().any(|v| v.name == face.subset) {
+                subsets.push(FontSubset::new(&face.subset, face.unicode_range.clone()));
+            }
+        }
+        if subsets.is_empty() {
+            return Err(Error::Generic(format!("the CSS2 API has none of the subsets {} for {}", nice_list(&spec.subsets, "or"), spec.id)))
+        }
+        warn_missing_subsets(&spec.id, &spec.subsets, &subsets);
+        let (kept, dropped): (Vec<_>, Vec<_>) = variants.into_iter()
+            .partition(|variant| faces.iter().any(|face| face.variant == *variant && subsets.iter().any(|v| v.name == face.subset)));
+        warn_dropped_variants(&spec.id, &dropped);
+        let variants = kept;
+
+        let description = match family {
+            Some(family) => family.into(),
+            None => FpmFontDescription::new(
+                css2::family_name(&spec.id),
+                &spec.id,
+                faces.first().and_then(|v| css2::version_from_url(&v.url)).unwrap_or_default()
+            )
+        };
+        Ok((DefinedFontInstallSpec::new(&spec.id, variants).with_subsets(subsets), description))
+    }
+
+    /// Like [`Source::download_subsets`], but with the files from the CSS2 API.
+    /// Variants that the API has no file for in a subset are left out.
+    async fn download_css2(&self, spec: &DefinedFontInstallSpec, dir: &Path) -> Result<HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>>, Error> {
+        let dir = dir.join(&spec.id);
+        let faces: Vec<Css2Face> = self.css2_faces(&spec.id, &spec.styles).await?.into_iter()
+            .filter(|face| spec.styles.contains(&face.variant) && spec.subsets.iter().any(|v| v.name == face.subset))
+            .collect();
+        let downloads = faces.iter()
+            .map(|face| Download {
+                remote_file: face.url.clone(),
+                urls: vec![face.url.clone()],
+                integrity: FileIntegrity::default(),
+                dir: dir.join(variant_to_string(&face.variant))
+            })
+            .collect();
+
+        let mut paths: HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>> = HashMap::new();
+        for (face, path) in faces.into_iter().zip(self.download_all(downloads).await?) {
+            paths.entry(face.variant).or_default().insert(face.subset, path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Warns about the subsets that were `requested` for the family `id`, but aren't in `found`.
+fn warn_missing_subsets(id: &str, requested: &[String], found: &[FontSubset]) {
+    let missing: Vec<&String> = requested.iter().filter(|name| !found.iter().any(|v| &v.name == *name)).collect();
+    if !missing.is_empty() {
+        warning!("[{}] {} has no subset{} {}, so only {} will be installed", GoogleFontsSource::ID, id, plural_s(missing.len()), nice_list(missing, "or"),
+            nice_list(found.iter().map(|v| &v.name), "and"));
+    }
+}
+
+/// Warns about the variants of the family `id` that aren't installed because they're missing from a subset.
+fn warn_dropped_variants(id: &str, dropped: &[DefinedFontVariantSpec]) {
+    if !dropped.is_empty() {
+        warning!("[{}] Not installing {} of {}: missing from some of the subsets", GoogleFontsSource::ID, nice_list(dropped.iter().map(variant_to_string), "and"), id);
+    }
+}
+
+/// Removes a partial download, along with where it came from.
+fn remove_part(part: &Path) {
+    let _ = remove_file(part);
+    let _ = remove_file(PartValidators::path_for(part));
 }
 
 #[async_trait]
 impl<'host> Source<'host> for GoogleFontsSource<'host> {
-    fn id(&self) -> &'host str {
+    fn id(&self) -> &str {
         return Self::ID;
     }
-    fn name(&self) -> &'host str {
+    fn name(&self) -> &str {
         return Self::NAME;
     }
 
     fn set_host(&mut self, host: &'host dyn FpmHost) {
         self.host = host;
+        self.index = RwLock::new(None);
+        self.css2_responses = RwLock::new(HashMap::new());
+        self.downloads = Semaphore::new(host.max_concurrent_downloads().max(1));
+        self.config = match host.config(Self::ID.into()).map(GoogleFontsConfig::from_toml) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                warning!("[{}] {} - using the default configuration", Self::ID, e);
+                GoogleFontsConfig::default()
+            },
+            None => GoogleFontsConfig::default()
+        };
         self.client = Some(
             ClientBuilder::new()
                 .user_agent(host.user_agent())
@@ -175,70 +611,187 @@ impl<'host> Source<'host> for GoogleFontsSource<'host> {
 
     async fn refresh(&self, force: bool) -> Result<RefreshOutput, Error> {
         let cache_file = self.cache_file(DATA_FILE);
-        let latest = self.latest_commit().await?;
-        trace!("[{}] Latest commit: {}", Self::ID, latest);
-
-        if !force {
-            let current = self.last_downloaded_commit();
-            trace!("[{}] Last downloaded commit: {}", Self::ID, current.clone().unwrap_or("<none>".into()));
-            if current != None && cache_file.exists() && current.unwrap() == latest {
-                return Ok(RefreshOutput::AlreadyUpToDate)
+        let validators = if force || !cache_file.exists() {
+            None
+        } else {
+            let validators = self.cache_read_deserialise::<IndexValidators, _>(VALIDATORS_FILE)
+                .ok()
+                .filter(|v| !v.is_empty());
+            // without validators from the last download, fall back to comparing the commit of the data branch
+            if validators.is_none() {
+                let latest = self.latest_commit().await?;
+                trace!("[{}] Latest commit: {}", Self::ID, latest);
+                let current = self.last_downloaded_commit();
+                trace!("[{}] Last downloaded commit: {}", Self::ID, current.clone().unwrap_or("<none>".into()));
+                if current.as_ref() == Some(&latest) {
+                    return Ok(RefreshOutput::AlreadyUpToDate)
+                }
             }
-        }
+            validators
+        };
 
-        let index = self.get_data().await?;
-        self.cache_write_serialise(DATA_FILE, &index)?;
-        self.cache_write_str(COMMIT_FILE, latest)?;
+        let (index, validators) = match self.get_data(validators.as_ref()).await? {
+            Some(v) => v,
+            None => return Ok(RefreshOutput::AlreadyUpToDate)
+        };
+        self.replace_data(&index)?;
+        self.cache_write_serialise(VALIDATORS_FILE, &validators)?;
+        if validators.is_empty() {
+            // the server can't tell us whether the index changed, so remember the commit it came from instead
+            self.cache_write_str(COMMIT_FILE, self.latest_commit().await?)?;
+        } else if self.cache_file(COMMIT_FILE).exists() {
+            std::fs::remove_file(self.cache_file(COMMIT_FILE))?;
+        }
 
         Ok(RefreshOutput::Downloaded)
     }
 
-    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FpmFontDescription), Error> {
-        let data = self.read_data()?;
+    async fn import_index(&self, index: &[u8]) -> Result<(), Error> {
+        let data = schema::parse(index).map_err(|e| match e {
+            Error::Deserialisation(e) => Error::Deserialisation(format!("not a valid {} index: {}", Self::NAME, e)),
+            e => e
+        })?;
+        for family in data.families().values() {
+            DefinedFontInstallSpec::try_from(family.clone())
+                .map_err(|e| Error::Deserialisation(format!("not a valid {} index: family {}: {}", Self::NAME, family.id, e)))?;
+        }
 
-        let family = data.get_family(&spec.id).ok_or(Error::NoSuchFamily(spec.id.clone()))?;
+        self.replace_data(&data)?;
+        // the imported index didn't come from the data branch, so the next refresh should replace it
+        let hash = Sha256::new().chain_update(index).finalize();
+        self.cache_write_str(COMMIT_FILE, format!("imported-{:x}", hash))?;
+        if self.cache_file(VALIDATORS_FILE).exists() {
+            std::fs::remove_file(self.cache_file(VALIDATORS_FILE))?;
+        }
 
-        Ok((family.clone().try_into()?, family.into()))
+        Ok(())
     }
 
-    async fn download_font(&self, font_id: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
-        let data = self.read_data()?;
+    async fn changes(&self) -> Result<Option<IndexChanges>, Error> {
+        if !self.cache_file(PREVIOUS_DATA_FILE).exists() {
+            return Ok(None)
+        }
+        let previous = schema::parse(&std::fs::read(self.cache_file(PREVIOUS_DATA_FILE))?)?;
+
+        Ok(Some(self.read_data()?.changes_since(&previous)?))
+    }
 
-        let font = if let Some(desc) = data.get_family(&font_id.id) {
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FpmFontDescription), Error> {
+        if self.config.css2() {
+            return self.resolve_css2(spec).await
+        }
+        let index = self.index()?;
+        let family = index.get_family(&spec.id)?.ok_or(Error::NoSuchFamily(spec.id.clone()))?;
+
+        let mut install_spec: DefinedFontInstallSpec = family.clone().try_into()?;
+        install_spec.styles.retain(|available| {
+            let available = FontVariantSpec::from(*available);
+            spec.styles.iter().any(|requested| available.is_covered_by(requested))
+        });
+
+        if !spec.subsets.is_empty() {
+            let subsets: Vec<FontSubset> = spec.subsets.iter()
+                .filter(|name| family.subset_files.contains_key(*name))
+                .map(|name| FontSubset::new(name, index.unicode_range(name).cloned()))
+                .collect();
+            if subsets.is_empty() {
+                let mut available: Vec<&String> = family.subset_files.keys().collect();
+                available.sort();
+                return Err(Error::Generic(format!("{} has none of the subsets {} (available: {})", family.id, nice_list(&spec.subsets, "or"), if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    nice_list(available, "and")
+                })))
+            }
+            warn_missing_subsets(&spec.id, &spec.subsets, &subsets);
+            // only the variants that have a file in every subset
+            let (kept, dropped): (Vec<_>, Vec<_>) = install_spec.styles.into_iter()
+                .partition(|variant| subsets.iter().all(|subset| family.subset_file_for(variant, &subset.name).is_some()));
+            warn_dropped_variants(&spec.id, &dropped);
+            install_spec.styles = kept;
+            install_spec = install_spec.with_subsets(subsets);
+        }
+
+        Ok((install_spec, family.into()))
+    }
+
+    async fn download_font(&self, font_id: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let font = if let Some(desc) = self.index()?.get_family(&font_id.id)? {
             desc
         } else {
             return Err(Error::Generic(format!("Font {} does not exist", font_id.id)))
         };
 
         let dir = dir.join(&font_id.id);
-        let mut paths = HashMap::new();
-
+        let mut downloads = Vec::new();
         for variant in &font_id.styles {
             let variant_name = variant_to_string(variant);
-            let remote_file = match font.files.get(variant_name.as_str()) {
-                Some(file) => file.clone(),
+            let remote_file = match font.file_for(variant) {
+                Some(file) => file,
                 None => return Err(Error::Generic(format!("Could not get file for font variant {variant_name}")))
             };
+            downloads.push(self.download_of(&font, remote_file, dir.join(variant_name)));
+        }
 
-            let extension = PathBuf::from(&remote_file).extension().map_or(String::new(), |v| ".".to_string() + v.to_str().unwrap());
-            let url_hash = Sha256::new()
-                .chain_update(&remote_file)
-                .finalize();
-            let url_hash = format!("{:x}", url_hash);
-            let path = dir.join(variant_name).join(format!("{}{}", url_hash, extension));
+        let paths = self.download_all(downloads).await?;
+        Ok(font_id.styles.iter().copied().zip(paths).collect())
+    }
 
-            paths.insert(variant.clone(), path.clone());
-            if path.exists() {
-                continue;
-            }
-            path.parent().map(create_dir_all);
+    async fn download_subsets(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>>, Error> {
+        if self.config.css2() {
+            return self.download_css2(spec, dir).await
+        }
+        let font = self.index()?.get_family(&spec.id)?
+            .ok_or_else(|| Error::Generic(format!("Font {} does not exist", spec.id)))?;
 
-            let remote_data = reqwest::get("https://".to_string() + remote_file.as_str()).await?.error_for_status()?;
-            let mut file = File::create(&path)?;
-            let remote_data = remote_data.bytes().await?;
-            file.write_all(remote_data.as_ref())?;
+        let dir = dir.join(&spec.id);
+        let mut files = Vec::new();
+        let mut downloads = Vec::new();
+        for variant in &spec.styles {
+            let variant_name = variant_to_string(variant);
+            for subset in &spec.subsets {
+                let remote_file = match font.subset_file_for(variant, &subset.name) {
+                    Some(file) => file,
+                    None => return Err(Error::Generic(format!("Could not get file for font variant {variant_name} in subset {}", subset.name)))
+                };
+                files.push((*variant, subset.name.clone()));
+                downloads.push(self.download_of(&font, remote_file, dir.join(&variant_name)));
+            }
         }
 
+        let mut paths: HashMap<DefinedFontVariantSpec, HashMap<String, PathBuf>> = HashMap::new();
+        for ((variant, subset), path) in files.into_iter().zip(self.download_all(downloads).await?) {
+            paths.entry(variant).or_default().insert(subset, path);
+        }
         Ok(paths)
     }
+
+    async fn download_licenses(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<String, PathBuf>, Error> {
+        // the CSS2 API has no licenses, so they come from the index in both modes
+        let font = match self.indexed_family(&spec.id) {
+            Some(font) => font,
+            None if self.config.css2() => {
+                warning!("[{}] Not installing the license of {}, since it isn't in the index", Self::ID, spec.id);
+                return Ok(HashMap::new())
+            },
+            None => return Err(Error::Generic(format!("Font {} does not exist", spec.id)))
+        };
+
+        let dir = dir.join(&spec.id).join("licenses");
+        let downloads = font.license_files.values()
+            .map(|remote_file| self.download_of(&font, remote_file, dir.clone()))
+            .collect();
+        let paths = self.download_all(downloads).await?;
+        Ok(font.license_files.keys().cloned().zip(paths).collect())
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FpmFontDescription>, Error> {
+        Ok(self.index()?.search(query)?.into_iter().map(Into::into).collect())
+    }
+
+    async fn search_by_tags(&self, tags: &[String]) -> Result<Vec<TaggedFont>, Error> {
+        Ok(self.index()?.search_by_tags(tags)?.into_iter()
+            .map(|family| TaggedFont { size: family.size(), font: family.into() })
+            .collect())
+    }
 }
\ No newline at end of file
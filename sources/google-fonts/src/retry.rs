@@ -0,0 +1,77 @@
+//! Retrying downloads that fail for reasons that might go away by themselves.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use reqwest::StatusCode;
+use fontpm_api::Error;
+
+/// The longest time to wait between two attempts.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Why an attempt to download a file failed, and whether it's worth trying again.
+pub struct Failure {
+    pub error: Error,
+    pub transient: bool
+}
+
+impl Failure {
+    pub fn permanent(error: impl Into<Error>) -> Self {
+        Failure { error: error.into(), transient: false }
+    }
+    pub fn transient(error: impl Into<Error>) -> Self {
+        Failure { error: error.into(), transient: true }
+    }
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(error: reqwest::Error) -> Self {
+        Failure { transient: is_transient(&error), error: error.into() }
+    }
+}
+
+/// Whether a request that failed with `error` is worth trying again (e.g. because of a timeout or a reset connection).
+pub fn is_transient(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => is_transient_status(status),
+        None => error.is_timeout() || error.is_connect() || error.is_request() || error.is_body() || error.is_decode()
+    }
+}
+
+/// Whether a response with `status` is worth requesting again.
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// How long to wait before attempt number `attempt` (starting at 1 for the first retry):
+/// `base` doubled for every earlier retry, of which a random part between a half and all of it is used,
+/// so that downloads that failed at the same time don't all retry at the same time.
+pub fn delay(base: Duration, attempt: u32) -> Duration {
+    let delay = base.saturating_mul(1u32 << attempt.saturating_sub(1).min(16)).min(MAX_DELAY);
+    let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.mul_f64(0.5 + jitter / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_grow_exponentially_with_jitter() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=4 {
+            let full = base * 2u32.pow(attempt - 1);
+            let delay = delay(base, attempt);
+            assert!(delay >= full / 2 && delay <= full, "attempt {}: {:?}", attempt, delay);
+        }
+        assert!(delay(base, 100) <= MAX_DELAY);
+    }
+
+    #[test]
+    fn only_some_statuses_are_transient() {
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::FORBIDDEN));
+    }
+}
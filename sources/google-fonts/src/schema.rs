@@ -0,0 +1,228 @@
+//! The versions of the index format.
+//!
+//! An index says which version it uses in `schema_version`, which is 1 if it's missing.
+//! Every supported version is converted into [`Data`], which is also what the cached index is stored as.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use serde::Deserialize;
+use fontpm_api::Error;
+use crate::data::Data;
+
+/// The schema versions this version of FontPM can read.
+pub const SUPPORTED: RangeInclusive<u64> = 1..=2;
+
+/// Just the version of an index, so that the rest of it is only read once the version is known.
+#[derive(Deserialize)]
+struct Version {
+    #[serde(default = "Version::first", alias = "schemaVersion")]
+    schema_version: u64
+}
+
+impl Version {
+    fn first() -> u64 {
+        1
+    }
+}
+
+/// Reads an index of any supported schema version.
+pub fn parse(json: &[u8]) -> Result<Data, Error> {
+    let version = serde_json::from_slice::<Version>(json)
+        .map_err(|e| Error::Deserialisation(format!("the index has an invalid schema version: {}", e)))?
+        .schema_version;
+
+    match version {
+        1 => serde_json::from_slice(json).map_err(invalid),
+        2 => serde_json::from_slice::<v2::Index>(json).map(Into::into).map_err(invalid),
+        _ if version > *SUPPORTED.end() => Err(Error::Deserialisation(format!(
+            "the index uses schema version {}, but this version of FontPM only supports versions {} to {} - please upgrade FontPM",
+            version, SUPPORTED.start(), SUPPORTED.end()
+        ))),
+        _ => Err(Error::Deserialisation(format!("the index uses the unknown schema version {}", version)))
+    }
+}
+
+fn invalid(e: serde_json::Error) -> Error {
+    Error::Deserialisation(e.to_string())
+}
+
+/// Version 2, which describes every file with its size and digest, and adds axes, licenses, designers and categories.
+/// Tags are kept from version 1, and the category of a family is also one of its tags.
+mod v2 {
+    use super::*;
+    use crate::data::{FileDigest, FontAxis, FontDescription};
+
+    #[derive(Deserialize)]
+    pub struct Index {
+        families: HashMap<String, Family>,
+        /// The characters in each subset, as CSS `unicode-range` values.
+        #[serde(default)]
+        subsets: HashMap<String, String>
+    }
+
+    #[derive(Deserialize)]
+    struct Family {
+        name: String,
+        version: i32,
+        #[serde(default)]
+        category: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        designers: Vec<String>,
+        #[serde(default)]
+        last_modified: u64,
+        #[serde(default)]
+        license: Option<License>,
+        #[serde(default)]
+        axes: Vec<FontAxis>,
+        /// The fixed-weight fonts, by variant (e.g. `regular` or `700italic`).
+        fonts: HashMap<String, File>,
+        /// The variable fonts, by style.
+        #[serde(default)]
+        variable: HashMap<String, File>,
+        /// The fonts of each subset, by subset and then by variant like `fonts`.
+        #[serde(default)]
+        subsets: HashMap<String, HashMap<String, File>>
+    }
+
+    #[derive(Deserialize)]
+    struct License {
+        /// e.g. `ofl` or `apache`
+        id: String,
+        #[serde(default)]
+        file: Option<File>
+    }
+
+    #[derive(Deserialize)]
+    struct File {
+        path: String,
+        #[serde(default)]
+        size: Option<u64>,
+        #[serde(default)]
+        sha256: Option<String>
+    }
+
+    impl File {
+        /// Records the digest of this file in `integrity`, and returns its path.
+        fn into_path(self, integrity: &mut HashMap<String, FileDigest>) -> String {
+            if self.size.is_some() || self.sha256.is_some() {
+                integrity.insert(self.path.clone(), FileDigest { size: self.size, sha256: self.sha256 });
+            }
+            self.path
+        }
+    }
+
+    impl Family {
+        fn into_description(self, id: String) -> FontDescription {
+            let mut integrity = HashMap::new();
+
+            let mut variants: Vec<String> = self.fonts.keys().cloned().collect();
+            // the order of `variants` is the order the fixed weights are installed in
+            variants.sort_by_key(|v| crate::data::description::string_to_variant(v).ok());
+            let files = self.fonts.into_iter()
+                .map(|(variant, file)| (variant, file.into_path(&mut integrity)))
+                .collect();
+            let variable_files = self.variable.into_iter()
+                .map(|(style, file)| (style, file.into_path(&mut integrity)))
+                .collect();
+            let subset_files = self.subsets.into_iter()
+                .map(|(subset, files)| (subset, files.into_iter().map(|(variant, file)| (variant, file.into_path(&mut integrity))).collect()))
+                .collect();
+            let (license, license_files) = match self.license {
+                Some(License { id, file: Some(file) }) => {
+                    let name = file.path.rsplit('/').next().unwrap_or_default().to_string();
+                    (Some(id), HashMap::from([(name, file.into_path(&mut integrity))]))
+                },
+                Some(License { id, file: None }) => (Some(id), HashMap::new()),
+                None => (None, HashMap::new())
+            };
+
+            let mut tags = self.tags;
+            if let Some(category) = &self.category {
+                if !tags.contains(category) {
+                    tags.insert(0, category.clone());
+                }
+            }
+
+            FontDescription {
+                id,
+                display_name: self.name,
+                version: self.version,
+                tags,
+                last_modified: self.last_modified,
+                files,
+                variants,
+                variable_files,
+                subset_files,
+                license_files,
+                integrity,
+                category: self.category,
+                designers: self.designers,
+                axes: self.axes,
+                license
+            }
+        }
+    }
+
+    impl From<Index> for Data {
+        fn from(value: Index) -> Self {
+            let families: HashMap<String, FontDescription> = value.families.into_iter()
+                .map(|(id, family)| (id.clone(), family.into_description(id)))
+                .collect();
+            let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+            for family in families.values() {
+                for tag in &family.tags {
+                    tags.entry(tag.clone()).or_default().push(family.id.clone());
+                }
+            }
+            tags.values_mut().for_each(|v| v.sort());
+
+            Data::new(families, tags, value.subsets)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight};
+
+    #[test]
+    fn reads_version_1() {
+        let data = parse(fontpm_testing::google_fonts::INDEX.as_bytes()).unwrap();
+        assert_eq!(data.families()["test-sans"].display_name, "Test Sans");
+        assert_eq!(data.tags()["monospace"], vec!["test-mono"]);
+    }
+
+    #[test]
+    fn reads_version_2() {
+        let data = parse(include_bytes!("../tests/fixtures/index.v2.json")).unwrap();
+        let family = &data.families()["test-sans"];
+        assert_eq!(family.display_name, "Test Sans");
+        assert_eq!(family.variants, vec!["regular", "italic", "700"]);
+        assert_eq!(family.category.as_deref(), Some("sans-serif"));
+        assert_eq!(family.designers, vec!["Test Foundry"]);
+        assert_eq!(family.license.as_deref(), Some("ofl"));
+        assert_eq!(family.axes[0].tag, "wght");
+        assert_eq!(family.license_files["OFL.txt"], "raw.example.com/google/fonts/main/ofl/testsans/OFL.txt");
+
+        let bold = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Regular };
+        let path = family.file_for(&bold).unwrap();
+        assert_eq!(path, "fonts.example.com/s/testsans/v3/TestSans-Bold.ttf");
+        assert_eq!(family.integrity_for(path).size, Some(49));
+        assert_eq!(family.subset_file_for(&DefinedFontVariantSpec::REGULAR, "latin").unwrap(), "fonts.example.com/s/testsans/v3/TestSans-Regular-latin.woff2");
+        assert_eq!(family.tags, vec!["sans-serif", "geometric"]);
+        assert_eq!(data.tags()["sans-serif"], vec!["test-sans"]);
+        assert_eq!(data.tags()["geometric"], vec!["test-sans"]);
+        assert_eq!(data.subsets()["cyrillic"], "U+0400-045F");
+    }
+
+    #[test]
+    fn asks_to_upgrade_for_newer_versions() {
+        let error = parse(br#"{"schema_version": 3, "families": []}"#).err().unwrap().to_string();
+        assert!(error.contains("schema version 3"));
+        assert!(error.contains("please upgrade FontPM"));
+        assert!(parse(br#"{"schema_version": "two"}"#).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+use reqwest::{RequestBuilder, Response};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+
+/// The cache validators the index was last downloaded with, used to make refreshing conditional.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct IndexValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>
+}
+
+impl IndexValidators {
+    pub fn from_response(response: &Response) -> Self {
+        let header = |name| response.headers().get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(ToString::to_string);
+        IndexValidators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Makes `request` conditional on the index having changed.
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// Where a partial download came from, saved next to it so that it's only resumed from the same version of the same file.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct PartValidators {
+    pub url: String,
+    #[serde(flatten)]
+    pub validators: IndexValidators
+}
+
+impl PartValidators {
+    pub fn from_response(url: &str, response: &Response) -> Self {
+        PartValidators {
+            url: url.to_string(),
+            validators: IndexValidators::from_response(response)
+        }
+    }
+
+    /// The file these are saved in for the partial download `part`.
+    pub fn path_for(part: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.json", part.display()))
+    }
+
+    pub fn read(part: &Path) -> Option<Self> {
+        serde_json::from_slice(&std::fs::read(Self::path_for(part)).ok()?).ok()
+    }
+
+    pub fn write(&self, part: &Path) -> std::io::Result<()> {
+        std::fs::write(Self::path_for(part), serde_json::to_vec(self)?)
+    }
+
+    /// The `If-Range` value that makes a range request conditional on the file being the one the part came from:
+    /// a strong ETag, or otherwise the modification date.
+    pub fn if_range(&self) -> Option<&String> {
+        let etag = self.validators.etag.as_ref().filter(|v| !v.starts_with("W/"));
+        etag.or(self.validators.last_modified.as_ref())
+    }
+}
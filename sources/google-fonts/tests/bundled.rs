@@ -0,0 +1,42 @@
+//! Run with `cargo test -p fontpm-source-google-fonts --features bundled-index`, optionally with `FONTPM_BUNDLED_INDEX=<path>`.
+//! The other tests expect to start without an index, so with a snapshot only run these (with `--test bundled`).
+#![cfg(feature = "bundled-index")]
+
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::font::FontInstallSpec;
+use fontpm_source_google_fonts::GoogleFontsSource;
+use fontpm_testing::TempFpmHost;
+
+/// The snapshot this was built with, if any.
+const SNAPSHOT: Option<&str> = option_env!("FONTPM_BUNDLED_INDEX");
+
+#[tokio::test]
+async fn works_before_the_first_refresh() {
+    if SNAPSHOT.is_none() {
+        return
+    }
+    let host = TempFpmHost::new();
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    let families = source.search("").await.unwrap();
+    assert!(!families.is_empty());
+    let spec = FontInstallSpec::new_all_styles(families[0].id.clone());
+    let (spec, _) = source.resolve_font(&spec).await.unwrap();
+    assert!(!spec.styles.is_empty());
+
+    // the snapshot isn't written to the cache, which would make it look like the index was downloaded
+    assert!(!host.cache_dir_for(source.id()).join("data.json").exists());
+}
+
+#[tokio::test]
+async fn needs_a_refresh_without_a_snapshot() {
+    if SNAPSHOT.is_some() {
+        return
+    }
+    let host = TempFpmHost::new();
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(matches!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await, Err(Error::IO(_))));
+}
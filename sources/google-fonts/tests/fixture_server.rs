@@ -0,0 +1,606 @@
+use std::time::Duration;
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription as FpmFontDescription, FontInstallSpec, FontStyle, FontSubset, FontVariantSpec, FontWeight};
+use fontpm_api::host::toml;
+use fontpm_api::source::RefreshOutput;
+use fontpm_source_google_fonts::GoogleFontsSource;
+use fontpm_testing::{google_fonts_fixture_server, FixtureResponse, FixtureServer, TempFpmHost};
+use fontpm_testing::google_fonts::{FILES, INDEX};
+use fontpm_testing::layout::{assert_file_contents, installed_files};
+
+/// The same families as `INDEX`, in version 2 of the index schema.
+const INDEX_V2: &str = include_str!("fixtures/index.v2.json");
+/// A response of the CSS2 API for the fixed weights of Test Sans, as recorded from a browser.
+const CSS2_TEST_SANS: &str = include_str!("fixtures/css2/test-sans.css");
+
+fn host_for(server: &FixtureServer) -> TempFpmHost {
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\nfile_base_url = \"{}\"\nretry_delay = 1",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data"),
+        server.url_for("/")
+    )).unwrap();
+    TempFpmHost::new().with_config(GoogleFontsSource::ID, config)
+}
+
+#[tokio::test]
+async fn refresh_and_resolve() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(matches!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await, Err(Error::IO(_))));
+
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+    assert!(source.refresh(true).await.unwrap() == RefreshOutput::Downloaded);
+    assert_eq!(server.request_count("/data/google-fonts.json"), 2);
+
+    let (spec, description) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert_eq!(description.name, "Test Sans");
+    assert_eq!(description.version, "3");
+    assert_eq!(spec.styles.len(), 5);
+
+    assert!(matches!(source.resolve_font(&FontInstallSpec::new_all_styles("no-such-font")).await, Err(Error::NoSuchFamily(_))));
+}
+
+#[tokio::test]
+async fn refresh_is_conditional() {
+    let server = google_fonts_fixture_server();
+    server.route("/data/google-fonts.json", FixtureResponse::ok(INDEX)
+        .with_header("ETag", "\"v1\"")
+        .with_header("Last-Modified", "Sun, 01 Jan 2023 00:00:00 GMT"));
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert!(host.cache_dir_for(source.id()).join("data.validators.json").exists());
+
+    server.route("/data/google-fonts.json", FixtureResponse::status(304, ""));
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+    let request = server.requests().into_iter().rfind(|v| v.path == "/data/google-fonts.json").unwrap();
+    assert_eq!(request.header("if-none-match"), Some("\"v1\""));
+    assert_eq!(request.header("if-modified-since"), Some("Sun, 01 Jan 2023 00:00:00 GMT"));
+    assert_eq!(server.request_count("/branches/data"), 0);
+
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.is_ok());
+}
+
+#[tokio::test]
+async fn commit_check_uses_github_token() {
+    let server = google_fonts_fixture_server();
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\ngithub_token = \"secret\"",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data")
+    )).unwrap();
+    let host = TempFpmHost::new().with_config(GoogleFontsSource::ID, config);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+    let requests: Vec<_> = server.requests().into_iter().filter(|v| v.path == "/branches/data").collect();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|v| v.header("authorization") == Some("Bearer secret")));
+    assert_eq!(server.request_count("/data/google-fonts.json"), 1);
+}
+
+#[tokio::test]
+async fn import_index() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(matches!(source.import_index(b"{\"families\": []}").await, Err(Error::Deserialisation(_))));
+    assert!(matches!(source.import_index(INDEX.replace("\"700\"]", "\"bold\"]").as_bytes()).await, Err(Error::Deserialisation(_))));
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.is_err());
+
+    source.import_index(INDEX.as_bytes()).await.unwrap();
+    let (_, description) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert_eq!(description.name, "Test Sans");
+    assert!(server.requests().is_empty());
+
+    // the next refresh replaces the imported index
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+}
+
+#[tokio::test]
+async fn index_schema_versions() {
+    let server = google_fonts_fixture_server();
+    server.route("/data/google-fonts.json", FixtureResponse::ok(INDEX_V2));
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    let (spec, description) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert_eq!(description.version, "3");
+    assert_eq!(spec.styles.len(), 5);
+    let found: Vec<_> = source.search("another foundry").await.unwrap().into_iter().map(|v| v.id).collect();
+    assert_eq!(found, vec!["test-mono"]);
+
+    server.route("/data/google-fonts.json", FixtureResponse::ok(r#"{"schema_version": 3, "families": {}}"#));
+    match source.refresh(true).await {
+        Err(Error::Deserialisation(message)) => assert!(message.contains("please upgrade FontPM"), "{}", message),
+        _ => panic!("expected the refresh to fail")
+    }
+    assert!(matches!(source.import_index(b"{\"schema_version\": 3}").await, Err(Error::Deserialisation(message)) if message.contains("please upgrade FontPM")));
+    // the index from before is kept
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.is_ok());
+}
+
+/// The fixture index, a while later.
+fn updated_index() -> String {
+    let mut index: serde_json::Value = serde_json::from_str(INDEX).unwrap();
+    let families = index["families"].as_object_mut().unwrap();
+    families.remove("test-mono");
+    families["test-sans"]["version"] = 4.into();
+    families["test-sans"]["variants"].as_array_mut().unwrap().push("700italic".into());
+    families["test-sans"]["files"]["700italic"] = "fonts.example.com/s/testsans/v4/TestSans-BoldItalic.ttf".into();
+    let mut serif = families["test-sans"].clone();
+    serif["id"] = "test-serif".into();
+    serif["display_name"] = "Test Serif".into();
+    families.insert("test-serif".into(), serif);
+    index.to_string()
+}
+
+#[tokio::test]
+async fn changes_since_previous_index() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    source.refresh(false).await.unwrap();
+    assert!(source.changes().await.unwrap().is_none());
+
+    server.route("/data/google-fonts.json", FixtureResponse::ok(updated_index()));
+    source.refresh(true).await.unwrap();
+    let changes = source.changes().await.unwrap().unwrap();
+    let ids = |fonts: &[FpmFontDescription]| fonts.iter().map(|v| v.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&changes.added), vec!["test-serif"]);
+    assert_eq!(ids(&changes.removed), vec!["test-mono"]);
+    assert_eq!(changes.updated.len(), 1);
+    assert_eq!(changes.updated[0].font.id, "test-sans");
+    assert_eq!(changes.updated[0].previous_version.as_deref(), Some("3"));
+    assert_eq!(changes.updated[0].added_variants, vec![DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Italic }]);
+
+    source.refresh(true).await.unwrap();
+    assert!(source.changes().await.unwrap().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn index_follows_data_json() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+    assert!(host.cache_dir_for(source.id()).join("data.bin").exists());
+
+    // data.json replaced behind the back of the binary index
+    std::fs::write(host.cache_dir_for(source.id()).join("data.json"), updated_index()).unwrap();
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    let (_, description) = source.resolve_font(&FontInstallSpec::new_all_styles("test-serif")).await.unwrap();
+    assert_eq!(description.name, "Test Serif");
+    assert!(matches!(source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await, Err(Error::NoSuchFamily(_))));
+}
+
+#[tokio::test]
+async fn download_uses_cache() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let cache_dir = host.cache_dir_for(source.id());
+    let paths = source.download_font(&spec, &cache_dir).await.unwrap();
+    assert_eq!(paths.len(), 5);
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_eq!(installed_files(cache_dir.join("test-sans")).len(), 5);
+
+    let again = source.download_font(&spec, &cache_dir).await.unwrap();
+    assert_eq!(paths, again);
+    assert_eq!(server.request_count(&format!("/{}", FILES[0])), 1);
+    assert!(server.requests().iter().all(|v| v.header("user-agent") == Some(host.user_agent().as_str())));
+}
+
+#[tokio::test]
+async fn broken_cached_files_are_downloaded_again() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let cache_dir = host.cache_dir_for(source.id());
+    let paths = source.download_font(&spec, &cache_dir).await.unwrap();
+    std::fs::write(&paths[&DefinedFontVariantSpec::REGULAR], "<html>captive portal</html>").unwrap();
+
+    let again = source.download_font(&spec, &cache_dir).await.unwrap();
+    assert_eq!(paths, again);
+    assert_file_contents(&again[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_eq!(server.request_count(&format!("/{}", FILES[0])), 2);
+    // files without a digest in the index are trusted
+    assert_eq!(server.request_count(&format!("/{}", FILES[1])), 1);
+}
+
+#[tokio::test]
+async fn downloads_are_concurrent_up_to_the_limit() {
+    let server = google_fonts_fixture_server();
+    for file in FILES {
+        server.route(format!("/{}", file), FixtureResponse::ok(*file).with_delay(Duration::from_millis(100)));
+    }
+    let host = host_for(&server).with_max_concurrent_downloads(2);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(paths.len(), 5);
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_eq!(server.max_concurrent_requests(), 2);
+    // nothing is left half-written
+    let cached = installed_files(host.cache_dir_for(source.id()).join("test-sans"));
+    assert!(cached.iter().all(|v| !v.ends_with(".part")), "{:?}", cached);
+}
+
+#[tokio::test]
+async fn download_fails_on_integrity_mismatch() {
+    let server = google_fonts_fixture_server();
+    server.route(format!("/{}", FILES[0]), FixtureResponse::ok("<html>captive portal</html>"));
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    match source.download_font(&spec, &host.cache_dir_for(source.id())).await {
+        Err(Error::Integrity(message)) => assert!(message.contains(FILES[0])),
+        other => panic!("expected an integrity error, got {:?}", other)
+    }
+    assert!(!installed_files(host.cache_dir_for(source.id()).join("test-sans")).iter().any(|v| v.starts_with("regular")));
+}
+
+#[tokio::test]
+async fn downloads_licenses() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let licenses = source.download_licenses(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(licenses.len(), 1);
+    assert_file_contents(&licenses["OFL.txt"], FILES[10]);
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    assert!(source.download_licenses(&spec, &host.cache_dir_for(source.id())).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn variable_fonts() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let variable_italic = DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Italic };
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new("test-sans", vec![FontVariantSpec {
+        weight: FontWeight::Defined(DefinedFontWeight::Variable),
+        style: FontStyle::All
+    }])).await.unwrap();
+    assert_eq!(spec.styles, vec![
+        DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Regular },
+        variable_italic
+    ]);
+
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(paths.len(), 2);
+    assert_file_contents(&paths[&variable_italic], FILES[5]);
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new("test-sans", vec![FontVariantSpec {
+        weight: FontWeight::AllFixed,
+        style: FontStyle::All
+    }])).await.unwrap();
+    assert_eq!(spec.styles.len(), 3);
+    assert!(spec.styles.iter().all(|v| v.weight != DefinedFontWeight::Variable));
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    assert_eq!(spec.styles, vec![DefinedFontVariantSpec::REGULAR]);
+}
+
+#[tokio::test]
+async fn subsets() {
+    let server = google_fonts_fixture_server();
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let bold = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Regular };
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans").with_subsets(["latin", "greek"])).await.unwrap();
+    // greek isn't available, and only regular and bold have subset files
+    assert_eq!(spec.subsets, vec![FontSubset::new("latin", Some("U+0000-00FF".to_string()))]);
+    assert_eq!(spec.styles, vec![DefinedFontVariantSpec::REGULAR, bold]);
+
+    let paths = source.download_subsets(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[&bold].len(), 1);
+    assert_file_contents(&paths[&bold]["latin"], FILES[7]);
+    assert_eq!(server.request_count(&format!("/{}", FILES[0])), 0);
+
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-sans").with_subsets(["greek"])).await.is_err());
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-mono").with_subsets(["latin"])).await.is_err());
+}
+
+#[tokio::test]
+async fn download_fails_over_to_next_mirror() {
+    let server = google_fonts_fixture_server();
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\nmirrors = [\"http://127.0.0.1:1/\", \"{}\", \"{}\"]\nretry_delay = 1",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data"),
+        server.url_for("/missing/"),
+        server.url_for("/")
+    )).unwrap();
+    let host = TempFpmHost::new().with_config(GoogleFontsSource::ID, config);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[3]);
+    assert_eq!(server.request_count(&format!("/missing/{}", FILES[3])), 1);
+    assert_eq!(server.request_count(&format!("/{}", FILES[3])), 1);
+
+    server.route(format!("/{}", FILES[0]), FixtureResponse::status(500, "unavailable"));
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert!(source.download_font(&spec, &host.cache_dir_for(source.id())).await.is_err());
+}
+
+#[tokio::test]
+async fn download_fails_on_missing_file() {
+    let server = google_fonts_fixture_server();
+    server.route(format!("/{}", FILES[3]), FixtureResponse::status(404, "not found"));
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    assert!(source.download_font(&spec, &host.cache_dir_for(source.id())).await.is_err());
+    assert!(installed_files(host.cache_dir_for(source.id()).join("test-mono")).is_empty());
+    // a missing file won't appear by trying again
+    assert_eq!(server.request_count(&format!("/{}", FILES[3])), 1);
+}
+
+#[tokio::test]
+async fn transient_errors_are_retried() {
+    let server = google_fonts_fixture_server();
+    server.route_sequence(format!("/{}", FILES[3]), vec![
+        FixtureResponse::status(500, "internal server error"),
+        FixtureResponse::status(503, "unavailable"),
+        FixtureResponse::ok(FILES[3])
+    ]);
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[3]);
+    assert_eq!(server.request_count(&format!("/{}", FILES[3])), 3);
+
+    // the default is 3 retries
+    server.route(format!("/{}", FILES[0]), FixtureResponse::status(503, "unavailable"));
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert!(source.download_font(&spec, &host.cache_dir_for(source.id())).await.is_err());
+    assert_eq!(server.request_count(&format!("/{}", FILES[0])), 4);
+}
+
+#[tokio::test]
+async fn interrupted_downloads_are_resumed() {
+    let server = google_fonts_fixture_server();
+    let path = format!("/{}", FILES[0]);
+    server.route_sequence(&path, vec![
+        FixtureResponse::ok(FILES[0]).with_range_support().with_header("ETag", "\"v3\"").interrupted_after(10),
+        FixtureResponse::ok(FILES[0]).with_range_support().with_header("ETag", "\"v3\"")
+    ]);
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    let requests: Vec<_> = server.requests().into_iter().filter(|v| v.path == path).collect();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("range"), None);
+    assert_eq!(requests[1].header("range"), Some("bytes=10-"));
+    assert_eq!(requests[1].header("if-range"), Some("\"v3\""));
+    let cached = installed_files(host.cache_dir_for(source.id()).join("test-sans"));
+    assert!(cached.iter().all(|v| !v.contains(".part")), "{:?}", cached);
+}
+
+#[tokio::test]
+async fn unverifiable_partial_downloads_start_over() {
+    // Test Mono has no digest in the index, and the server sends no validators
+    let server = google_fonts_fixture_server();
+    let path = format!("/{}", FILES[3]);
+    server.route_sequence(&path, vec![
+        FixtureResponse::ok(FILES[3]).with_range_support().interrupted_after(10),
+        FixtureResponse::ok(FILES[3]).with_range_support()
+    ]);
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[3]);
+    let requests: Vec<_> = server.requests().into_iter().filter(|v| v.path == path).collect();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].header("range"), None);
+}
+
+#[tokio::test]
+async fn partial_downloads_from_another_mirror_start_over() {
+    let server = google_fonts_fixture_server();
+    server.route_sequence(format!("/first/{}", FILES[0]), vec![
+        FixtureResponse::ok(FILES[0]).with_range_support().with_header("ETag", "\"v3\"").interrupted_after(10),
+        FixtureResponse::status(404, "not found")
+    ]);
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\nmirrors = [\"{}\", \"{}\"]\nretry_delay = 1",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data"),
+        server.url_for("/first/"),
+        server.url_for("/")
+    )).unwrap();
+    let host = TempFpmHost::new().with_config(GoogleFontsSource::ID, config);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_eq!(server.request_count(&format!("/first/{}", FILES[0])), 2);
+    let last = server.requests().into_iter().rfind(|v| v.path == format!("/{}", FILES[0])).unwrap();
+    assert_eq!(last.header("range"), None);
+}
+
+#[tokio::test]
+async fn interrupted_downloads_start_over_without_range_support() {
+    let server = google_fonts_fixture_server();
+    let path = format!("/{}", FILES[0]);
+    server.route_sequence(&path, vec![
+        FixtureResponse::ok(FILES[0]).interrupted_after(10),
+        FixtureResponse::ok(FILES[0])
+    ]);
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_eq!(server.request_count(&path), 2);
+}
+
+#[tokio::test]
+async fn failed_downloads_are_resumed_by_the_next_attempt() {
+    let server = google_fonts_fixture_server();
+    let path = format!("/{}", FILES[0]);
+    server.route(&path, FixtureResponse::ok(FILES[0]).with_range_support().interrupted_after(10));
+    let host = host_for(&server);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    let cache_dir = host.cache_dir_for(source.id());
+    assert!(source.download_font(&spec, &cache_dir).await.is_err());
+    let cached = installed_files(cache_dir.join("test-sans"));
+    assert!(cached.iter().any(|v| v.ends_with(".part")), "{:?}", cached);
+
+    server.route(&path, FixtureResponse::ok(FILES[0]).with_range_support());
+    let count = server.request_count(&path);
+    let paths = source.download_font(&spec, &cache_dir).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    let requests = server.requests();
+    let last = requests.iter().rev().find(|v| v.path == path).unwrap();
+    assert_eq!(server.request_count(&path), count + 1);
+    assert_eq!(last.header("range"), Some("bytes=10-"));
+    let cached = installed_files(cache_dir.join("test-sans"));
+    assert!(cached.iter().all(|v| !v.ends_with(".part")), "{:?}", cached);
+}
+
+#[tokio::test]
+async fn css2_api() {
+    let server = google_fonts_fixture_server();
+    // replays the recorded response, with the font files on the fixture server
+    server.route(
+        "/css2?family=Test+Sans:ital,wght@0,400;0,700;1,400&display=swap",
+        FixtureResponse::ok(CSS2_TEST_SANS.replace("https://fonts.gstatic.com/", &server.url_for("/")))
+    );
+    for file in ["TestSans-Regular-latin", "TestSans-Bold-latin", "TestSans-Italic-latin"] {
+        server.route(format!("/s/testsans/v3/{}.woff2", file), FixtureResponse::ok(file));
+    }
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\ncss2 = true\ncss2_url = \"{}\"",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data"),
+        server.url_for("/css2")
+    )).unwrap();
+    let host = TempFpmHost::new().with_config(GoogleFontsSource::ID, config);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+
+    let italic = DefinedFontVariantSpec { weight: DefinedFontWeight::REGULAR, style: DefinedFontStyle::Italic };
+    let spec = FontInstallSpec::new("test-sans", vec![FontVariantSpec { weight: FontWeight::AllFixed, style: FontStyle::All }]);
+    let (install_spec, description) = source.resolve_font(&spec.clone().with_subsets(["latin"])).await.unwrap();
+    assert_eq!(description.name, "Test Sans");
+    assert_eq!(install_spec.styles.len(), 3);
+    assert_eq!(install_spec.subsets, vec![FontSubset::new("latin", Some("U+0000-00FF, U+0131, U+2000-206F".to_string()))]);
+
+    let paths = source.download_subsets(&install_spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(paths.len(), 3);
+    assert_file_contents(&paths[&italic]["latin"], "TestSans-Italic-latin");
+    assert_eq!(server.request_count("/s/testsans/v3/TestSans-Italic-cyrillic.woff2"), 0);
+
+    // the response is only requested once, like a browser would
+    let css2_requests: Vec<_> = server.requests().into_iter().filter(|v| v.path.starts_with("/css2")).collect();
+    assert_eq!(css2_requests.len(), 1);
+    assert!(css2_requests[0].header("user-agent").unwrap().starts_with("Mozilla/5.0"));
+
+    let (install_spec, _) = source.resolve_font(&spec).await.unwrap();
+    assert_eq!(install_spec.subsets.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["cyrillic", "latin"]);
+    assert!(source.resolve_font(&spec.with_subsets(["greek"])).await.is_err());
+}
+
+#[tokio::test]
+async fn css2_api_without_index() {
+    let server = google_fonts_fixture_server();
+    // without the index, every weight is requested as a variable font
+    server.route(
+        "/css2?family=Test+Sans:ital,wght@0,100..900&display=swap",
+        FixtureResponse::ok(format!(
+            "/* latin */\n@font-face {{\n  font-family: 'Test Sans';\n  font-style: normal;\n  font-weight: 100 900;\n  font-display: swap;\n  src: url({}) format('woff2');\n  unicode-range: U+0000-00FF;\n}}\n",
+            server.url_for("/s/testsans/v3/TestSans-Variable-latin.woff2")
+        ))
+    );
+    let config: toml::Value = toml::from_str(&format!(
+        "index_url = \"{}\"\ncommit_url = \"{}\"\ncss2 = true\ncss2_url = \"{}\"",
+        server.url_for("/data/google-fonts.json"),
+        server.url_for("/branches/data"),
+        server.url_for("/css2")
+    )).unwrap();
+    let host = TempFpmHost::new().with_config(GoogleFontsSource::ID, config);
+    let mut source = GoogleFontsSource::new();
+    source.set_host(&host);
+
+    let (install_spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+    assert_eq!(install_spec.styles, vec![DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Regular }]);
+
+    let all_fixed = FontInstallSpec::new("test-sans", vec![FontVariantSpec { weight: FontWeight::AllFixed, style: FontStyle::All }]);
+    assert!(source.resolve_font(&all_fixed).await.is_err());
+}
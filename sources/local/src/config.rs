@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+use serde::Deserialize;
+use fontpm_api::Error;
+use fontpm_api::host::toml;
+
+/// Configuration from `[sources.local]`.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct LocalConfig {
+    /// The directories to find fonts in, including their subdirectories.
+    directories: Vec<PathBuf>
+}
+
+impl LocalConfig {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, Error> {
+        value.clone().try_into::<Self>()
+            .map_err(|v| Error::Deserialisation(format!("invalid local configuration: {}", v)))
+    }
+
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+}
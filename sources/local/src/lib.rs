@@ -0,0 +1,196 @@
+//! A source for fonts that are already on disk (e.g. on a shared drive of licensed fonts),
+//! which are found by scanning the directories in `[sources.local]` and reading the files' names and styles.
+//! Fonts are "downloaded" by using the files where they are.
+
+mod config;
+mod scan;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use fontpm_api::{FpmHost, Source, trace, warning};
+use fontpm_api::async_trait::async_trait;
+use fontpm_api::host::EmptyFpmHost;
+use fontpm_api::registry::SourceFactory;
+use fontpm_api::source::RefreshOutput;
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontDescription, FontInstallSpec, FontVariantSpec};
+use fontpm_api::util::create_parent;
+use crate::config::LocalConfig;
+use crate::scan::{Family, Scan};
+
+/// The result of the last scan, so that the next one only has to read the files that changed.
+const SCAN_FILE: &str = "scan.json";
+
+pub struct LocalSource<'host> {
+    host: &'host dyn FpmHost,
+    config: LocalConfig,
+    /// The last scan, once it has been loaded.
+    scan: RwLock<Option<Arc<Scan>>>
+}
+
+fn create_source(host: &dyn FpmHost) -> Box<dyn Source<'_> + '_> {
+    let mut source = LocalSource::new();
+    source.set_host(host);
+    Box::new(source)
+}
+fontpm_api::register_source!(SourceFactory::new(LocalSource::ID, LocalSource::NAME, false, create_source));
+
+impl From<Family> for FontDescription {
+    fn from(value: Family) -> Self {
+        FontDescription::new(value.name, value.id, value.version)
+    }
+}
+
+impl<'host> LocalSource<'host> {
+    pub const ID: &'host str = "local";
+    pub const NAME: &'host str = "Local directories";
+
+    pub fn new() -> Self {
+        LocalSource {
+            host: &EmptyFpmHost::EMPTY_HOST,
+            config: LocalConfig::default(),
+            scan: RwLock::new(None)
+        }
+    }
+
+    fn scan_file(&self) -> PathBuf {
+        self.host.cache_dir_for(Self::ID).join(SCAN_FILE)
+    }
+
+    /// The last scan that was saved, if there is one.
+    fn saved_scan(&self) -> Result<Option<Scan>, Error> {
+        let path = self.scan_file();
+        if !path.exists() {
+            return Ok(None)
+        }
+        match serde_json::from_slice(&std::fs::read(&path)?) {
+            Ok(scan) => Ok(Some(scan)),
+            Err(e) => {
+                trace!("[{}] Scanning everything again, as {} is invalid: {}", Self::ID, SCAN_FILE, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Scans the configured directories, reading only the files that changed since `previous`, and saves the result.
+    fn rescan(&self, previous: &Scan) -> Result<Arc<Scan>, Error> {
+        if self.config.directories().is_empty() {
+            return Err(Error::Generic(format!("no directories are configured - add them to `directories` in [sources.{}]", Self::ID)))
+        }
+        let scan = previous.rescan(self.config.directories())?;
+
+        let path = self.scan_file();
+        create_parent(&path)?;
+        let json = serde_json::to_vec(&scan).map_err(|e| Error::Serialisation(e.to_string()))?;
+        std::fs::write(path, json)?;
+
+        let scan = Arc::new(scan);
+        *self.scan.write().unwrap() = Some(scan.clone());
+        Ok(scan)
+    }
+
+    /// The last scan, which is loaded the first time it's needed. If the directories have never been scanned, they're scanned now.
+    fn scan(&self) -> Result<Arc<Scan>, Error> {
+        if let Some(scan) = self.scan.read().unwrap().as_ref() {
+            return Ok(scan.clone())
+        }
+        match self.saved_scan()? {
+            Some(scan) => {
+                let scan = Arc::new(scan);
+                *self.scan.write().unwrap() = Some(scan.clone());
+                Ok(scan)
+            },
+            None => self.rescan(&Scan::default())
+        }
+    }
+
+    fn family(&self, id: &str) -> Result<Family, Error> {
+        self.scan()?.families().into_iter()
+            .find(|v| v.id == id)
+            .ok_or_else(|| Error::NoSuchFamily(id.to_string()))
+    }
+}
+
+impl Default for LocalSource<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<'host> Source<'host> for LocalSource<'host> {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn set_host(&mut self, host: &'host dyn FpmHost) {
+        self.host = host;
+        self.scan = RwLock::new(None);
+        self.config = match host.config(Self::ID.into()).map(LocalConfig::from_toml) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                warning!("[{}] {} - using the default configuration", Self::ID, e);
+                LocalConfig::default()
+            },
+            None => LocalConfig::default()
+        };
+    }
+
+    async fn refresh(&self, force_refresh: bool) -> Result<RefreshOutput, Error> {
+        let previous = self.saved_scan()?.unwrap_or_default();
+        let scan = if force_refresh {
+            self.rescan(&Scan::default())?
+        } else {
+            self.rescan(&previous)?
+        };
+
+        if *scan == previous {
+            Ok(RefreshOutput::AlreadyUpToDate)
+        } else {
+            Ok(RefreshOutput::Downloaded)
+        }
+    }
+
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+        if !spec.subsets.is_empty() {
+            warning!("[{}] {} has no subsets, so it will be installed in full", Self::ID, spec.id);
+        }
+        let family = self.family(&spec.id)?;
+
+        let styles: Vec<DefinedFontVariantSpec> = family.files.keys()
+            .filter(|available| {
+                let available = FontVariantSpec::from(**available);
+                spec.styles.iter().any(|requested| available.is_covered_by(requested))
+            })
+            .copied()
+            .collect();
+
+        Ok((DefinedFontInstallSpec::new(&family.id, styles), family.into()))
+    }
+
+    async fn download_font(&self, spec: &DefinedFontInstallSpec, _dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let family = self.family(&spec.id)?;
+        spec.styles.iter()
+            .map(|variant| {
+                let path = family.files.get(variant)
+                    .ok_or_else(|| Error::Generic(format!("{} has no file for the variant {} {}", family.id, variant.weight, variant.style)))?;
+                if !path.exists() {
+                    return Err(Error::Generic(format!("{} no longer exists - run `fontpm refresh` to scan the directories again", path.display())))
+                }
+                Ok((*variant, path.clone()))
+            })
+            .collect()
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let query = query.to_lowercase();
+        Ok(self.scan()?.families().into_iter()
+            .filter(|v| v.id.contains(&query) || v.name.to_lowercase().contains(&query))
+            .map(Into::into)
+            .collect())
+    }
+}
@@ -0,0 +1,189 @@
+//! Finding font files in directories, and reading which family and variant they are from their `name` and `OS/2` tables.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use ttf_parser::{name_id, Face, Language};
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight};
+
+/// Extensions of the files that are read, in lowercase.
+const EXTENSIONS: &[&str] = &["ttf", "otf"];
+
+/// What was found in a file when it was last scanned.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScannedFile {
+    /// When the file was last modified, in nanoseconds since the Unix epoch.
+    pub modified: u64,
+    pub size: u64,
+    /// The font in the file, or `None` if it couldn't be read as one (so that it isn't read again until it changes).
+    pub font: Option<FontFile>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FontFile {
+    pub family: String,
+    /// The weight class, or `None` for variable fonts.
+    pub weight: Option<u32>,
+    pub italic: bool,
+    pub version: String
+}
+
+impl FontFile {
+    /// Reads the family, variant and version of the font in `data`.
+    pub fn read(data: &[u8]) -> Result<Self, Error> {
+        let face = Face::parse(data, 0).map_err(|e| Error::Deserialisation(e.to_string()))?;
+        let family = name(&face, name_id::TYPOGRAPHIC_FAMILY)
+            .or_else(|| name(&face, name_id::FAMILY))
+            .ok_or_else(|| Error::Deserialisation("the font has no family name".into()))?;
+        let version = name(&face, name_id::VERSION).map_or_else(|| "unknown".to_string(), |v| parse_version(&v));
+
+        Ok(FontFile {
+            family,
+            weight: if face.is_variable() { None } else { Some(face.weight().to_number() as u32) },
+            italic: face.is_italic() || face.is_oblique(),
+            version
+        })
+    }
+
+    pub fn variant(&self) -> DefinedFontVariantSpec {
+        DefinedFontVariantSpec {
+            weight: self.weight.map_or(DefinedFontWeight::Variable, DefinedFontWeight::Fixed),
+            style: if self.italic { DefinedFontStyle::Italic } else { DefinedFontStyle::Regular }
+        }
+    }
+}
+
+/// The name with `id`, preferably in American English, which is what most fonts have at least.
+fn name(face: &Face, id: u16) -> Option<String> {
+    let names: Vec<_> = face.names().into_iter().filter(|v| v.name_id == id).collect();
+    names.iter()
+        .filter(|v| v.language() == Language::English_UnitedStates)
+        .chain(names.iter())
+        .find_map(|v| v.to_string())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Turns a version name like `Version 2.015;PS 2.000;hotconv 1.0.88` into `2.015`.
+fn parse_version(name: &str) -> String {
+    let name = name.trim();
+    let name = match name.get(..8) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("version ") => &name[8..],
+        _ => name
+    };
+    name.split([';', ' ']).next().unwrap_or_default().to_string()
+}
+
+/// Makes an ID out of a family name, e.g. `proxima-nova` out of `Proxima Nova`.
+pub fn family_id(family: &str) -> String {
+    family.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A family made up of the files that have its name.
+#[derive(Clone, Debug)]
+pub struct Family {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub files: BTreeMap<DefinedFontVariantSpec, PathBuf>
+}
+
+/// Every file in the configured directories, as of the last scan.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct Scan {
+    pub files: BTreeMap<PathBuf, ScannedFile>
+}
+
+impl Scan {
+    /// Scans `directories` again, only reading the files that have been added or changed since this scan.
+    pub fn rescan(&self, directories: &[PathBuf]) -> Result<Scan, Error> {
+        let mut paths = Vec::new();
+        for directory in directories {
+            find_fonts(directory, &mut paths)
+                .map_err(|e| Error::Generic(format!("could not read {}: {}", directory.display(), e)))?;
+        }
+
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let metadata = path.metadata()?;
+            let modified = metadata.modified()?
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |v| v.as_nanos() as u64);
+            let size = metadata.len();
+
+            let scanned = match self.files.get(&path) {
+                Some(scanned) if scanned.modified == modified && scanned.size == size => scanned.clone(),
+                _ => ScannedFile { modified, size, font: FontFile::read(&std::fs::read(&path)?).ok() }
+            };
+            files.insert(path, scanned);
+        }
+        Ok(Scan { files })
+    }
+
+    /// The families in this scan, sorted by ID.
+    /// If several files have the same family and variant, the one with the first path is used.
+    pub fn families(&self) -> Vec<Family> {
+        let mut families: BTreeMap<String, Family> = BTreeMap::new();
+        for (path, file) in &self.files {
+            let font = match &file.font {
+                Some(v) => v,
+                None => continue
+            };
+            let family = families.entry(family_id(&font.family)).or_insert_with_key(|id| Family {
+                id: id.clone(),
+                name: font.family.clone(),
+                version: font.version.clone(),
+                files: BTreeMap::new()
+            });
+            if !family.files.contains_key(&font.variant()) {
+                // the version of a family is that of its regular variant, if it has one
+                if font.variant() == DefinedFontVariantSpec::REGULAR {
+                    family.version = font.version.clone();
+                }
+                family.files.insert(font.variant(), path.clone());
+            }
+        }
+        families.into_values()
+            .filter(|v| !v.id.is_empty())
+            .collect()
+    }
+}
+
+/// Adds every font file in `directory` and its subdirectories to `paths`.
+fn find_fonts(directory: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in directory.read_dir()? {
+        let entry = entry?;
+        let path = entry.path();
+        // symbolic links to directories aren't followed, so that links back up the tree can't make this loop forever
+        if entry.file_type()?.is_dir() {
+            find_fonts(&path, paths)?;
+        } else if path.extension().and_then(|v| v.to_str()).is_some_and(|v| EXTENSIONS.contains(&v.to_lowercase().as_str())) && path.is_file() {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version("Version 2.015;PS 2.000;hotconv 1.0.88"), "2.015");
+        assert_eq!(parse_version("version 1.10"), "1.10");
+        assert_eq!(parse_version("3.0"), "3.0");
+    }
+
+    #[test]
+    fn family_ids() {
+        assert_eq!(family_id("Proxima Nova"), "proxima-nova");
+        assert_eq!(family_id("  Test  Sans (Pro) "), "test-sans-pro");
+    }
+}
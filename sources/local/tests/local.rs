@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontInstallSpec};
+use fontpm_api::host::toml;
+use fontpm_api::source::RefreshOutput;
+use fontpm_source_local::LocalSource;
+use fontpm_testing::{FixtureFont, TempFpmHost};
+
+const BOLD: DefinedFontVariantSpec = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Regular };
+const ITALIC: DefinedFontVariantSpec = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(400), style: DefinedFontStyle::Italic };
+const VARIABLE: DefinedFontVariantSpec = DefinedFontVariantSpec { weight: DefinedFontWeight::Variable, style: DefinedFontStyle::Regular };
+
+fn write_font(path: impl AsRef<Path>, font: FixtureFont) {
+    let path = path.as_ref();
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, font.build()).unwrap();
+}
+
+/// A host whose local source scans `fonts` and `more-fonts` in its temporary directory,
+/// of which `fonts` has Proxima Nova in a few styles and some files that aren't fonts.
+fn host() -> TempFpmHost {
+    let mut host = TempFpmHost::new();
+    let fonts = host.path().join("fonts");
+    write_font(fonts.join("ProximaNova-Regular.otf"), FixtureFont::new("Proxima Nova").with_version("Version 2.015;PS 2.000"));
+    write_font(fonts.join("bold/ProximaNova-Bold.otf"), FixtureFont::new("Proxima Nova").with_weight(700));
+    write_font(fonts.join("ProximaNova-Italic.ttf"), FixtureFont::new("Proxima Nova").italic());
+    write_font(fonts.join("ProximaNova-Variable.ttf"), FixtureFont::new("Proxima Nova").variable());
+    fs::write(fonts.join("broken.ttf"), "not a font").unwrap();
+    fs::write(fonts.join("README.txt"), "licensed fonts").unwrap();
+    fs::create_dir_all(host.path().join("more-fonts")).unwrap();
+
+    let config: toml::Value = toml::from_str(&format!(
+        "directories = [\"{}\", \"{}\"]",
+        fonts.display(),
+        host.path().join("more-fonts").display()
+    )).unwrap();
+    host.set_config(LocalSource::ID, config);
+    host
+}
+
+#[tokio::test]
+async fn scan_and_resolve() {
+    let host = host();
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+
+    let (spec, description) = source.resolve_font(&FontInstallSpec::new_all_styles("proxima-nova")).await.unwrap();
+    assert_eq!(description.name, "Proxima Nova");
+    assert_eq!(description.version, "2.015");
+    let mut styles = spec.styles.clone();
+    styles.sort();
+    assert_eq!(styles, vec![VARIABLE, DefinedFontVariantSpec::REGULAR, ITALIC, BOLD]);
+
+    // files are used where they are
+    let paths = source.download_font(&spec, &host.cache_dir_for(source.id())).await.unwrap();
+    assert_eq!(paths[&BOLD], host.path().join("fonts/bold/ProximaNova-Bold.otf"));
+    assert_eq!(paths[&VARIABLE], host.path().join("fonts/ProximaNova-Variable.ttf"));
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new("proxima-nova", vec![BOLD])).await.unwrap();
+    assert_eq!(spec.styles, vec![BOLD]);
+
+    match source.resolve_font(&FontInstallSpec::new_all_styles("helvetica")).await {
+        Err(Error::NoSuchFamily(id)) => assert_eq!(id, "helvetica"),
+        other => panic!("expected a missing family, got {:?}", other.map(|v| v.1))
+    }
+    // without subsets, fonts are installed in full
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("proxima-nova").with_subsets(["latin"])).await.unwrap();
+    assert!(spec.subsets.is_empty());
+    assert_eq!(spec.styles.len(), 4);
+
+    let found = source.search("proxima").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, "proxima-nova");
+}
+
+#[tokio::test]
+async fn scans_on_first_use() {
+    let host = host();
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("proxima-nova")).await.unwrap();
+    assert_eq!(spec.styles.len(), 4);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+}
+
+#[tokio::test]
+async fn refresh_only_reads_changed_files() {
+    let host = host();
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+    source.refresh(false).await.unwrap();
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+
+    // a file that is replaced without changing its size or modification time isn't read again...
+    let path = host.path().join("fonts/ProximaNova-Italic.ttf");
+    let modified = path.metadata().unwrap().modified().unwrap();
+    write_font(&path, FixtureFont::new("Proxima Neue").italic());
+    fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("proxima-neue")).await.is_err());
+
+    // ...unless everything is read again
+    assert!(source.refresh(true).await.unwrap() == RefreshOutput::Downloaded);
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("proxima-neue")).await.unwrap();
+    assert_eq!(spec.styles, vec![ITALIC]);
+
+    // new, changed and removed files are noticed
+    write_font(host.path().join("more-fonts/TestMono.ttf"), FixtureFont::new("Test Mono"));
+    write_font(&path, FixtureFont::new("Proxima Nova").italic().with_version("Version 3.000"));
+    fs::File::options().write(true).open(&path).unwrap().set_modified(SystemTime::now() + std::time::Duration::from_secs(10)).unwrap();
+    fs::remove_file(host.path().join("fonts/bold/ProximaNova-Bold.otf")).unwrap();
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("test-mono")).await.is_ok());
+    assert!(source.resolve_font(&FontInstallSpec::new_all_styles("proxima-neue")).await.is_err());
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("proxima-nova")).await.unwrap();
+    assert_eq!(spec.styles.len(), 3);
+    assert!(!spec.styles.contains(&BOLD));
+
+    // a new source reads the saved scan instead of scanning again
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+}
+
+#[tokio::test]
+async fn needs_directories() {
+    let host = TempFpmHost::new();
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+    let error = source.refresh(false).await.err().unwrap();
+    assert!(error.to_string().contains("directories"), "{}", error);
+
+    let host = TempFpmHost::new().with_config(LocalSource::ID, toml::from_str("directories = [\"/no/such/directory\"]").unwrap());
+    let mut source = LocalSource::new();
+    source.set_host(&host);
+    assert!(source.refresh(false).await.is_err());
+}
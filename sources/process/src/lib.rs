@@ -0,0 +1,293 @@
+mod protocol;
+
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use fontpm_api::{debug, error, info, trace, warning, Error, FpmHost, Source};
+use fontpm_api::async_trait::async_trait;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontDescription, FontInstallSpec};
+use fontpm_api::host::EmptyFpmHost;
+use fontpm_api::source::RefreshOutput;
+use crate::protocol::{DescribeParams, DescribeResult, DownloadParams, DownloadResult, LogParams, Message, RefreshParams, RefreshResult, Request, ResolveResult, SearchParams, SearchResult, Spec};
+pub use crate::protocol::PROTOCOL_VERSION;
+
+/// Plugin executables are named `fontpm-source-<id>`.
+pub const EXECUTABLE_PREFIX: &str = "fontpm-source-";
+/// How long a plugin may go without sending a message while FontPM waits for a response.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginExecutable {
+    pub id: String,
+    pub path: PathBuf
+}
+
+fn plugin_id(path: &Path) -> Option<String> {
+    let name = if cfg!(windows) {
+        path.file_stem()
+    } else {
+        path.file_name()
+    }?.to_str()?;
+    let id = name.strip_prefix(EXECUTABLE_PREFIX)?;
+    if id.is_empty() {
+        None
+    } else {
+        Some(id.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|v| v.is_file() && v.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() && path.extension().map_or(false, |v| v.eq_ignore_ascii_case("exe"))
+}
+
+/// Finds all plugin executables, looking through `plugin_dirs` first and then `PATH`.
+/// If multiple executables share an ID, the first one found is used.
+pub fn discover<I, P>(plugin_dirs: I) -> Vec<PluginExecutable> where I: IntoIterator<Item = P>, P: AsRef<Path> {
+    let mut dirs: Vec<PathBuf> = plugin_dirs.into_iter().map(|v| v.as_ref().to_path_buf()).collect();
+    if let Some(path) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&path));
+    }
+
+    let mut found: Vec<PluginExecutable> = Vec::new();
+    for dir in dirs {
+        let entries = match dir.read_dir() {
+            Ok(v) => v,
+            Err(_) => continue
+        };
+        let mut executables: Vec<PluginExecutable> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let id = plugin_id(&path)?;
+                if !is_executable(&path) {
+                    return None
+                }
+                Some(PluginExecutable { id, path })
+            })
+            .collect();
+        executables.sort_by(|a, b| a.id.cmp(&b.id));
+        for executable in executables {
+            if !found.iter().any(|v| v.id == executable.id) {
+                found.push(executable);
+            }
+        }
+    }
+    found
+}
+
+/// Finds the plugin executable for a specific source ID. See [`discover`].
+pub fn find<I, P>(id: &str, plugin_dirs: I) -> Option<PluginExecutable> where I: IntoIterator<Item = P>, P: AsRef<Path> {
+    discover(plugin_dirs).into_iter().find(|v| v.id == id)
+}
+
+struct Connection {
+    /// The plugin process, which is checked for having exited and killed when it doesn't respond in time
+    /// (and, being spawned with `kill_on_drop`, when the connection is dropped).
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64
+}
+
+impl Connection {
+    fn spawn(executable: &Path) -> Result<Self, Error> {
+        let mut child = Command::new(executable)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Connection {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            next_id: 1
+        })
+    }
+
+    /// Whether the process has exited (or can't be waited for), in which case the connection can't be used again.
+    fn exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    async fn call<P, R>(&mut self, plugin_id: &str, method: &str, params: P, timeout: Duration) -> Result<R, Error> where P: Serialize, R: DeserializeOwned {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut line = serde_json::to_string(&Request { jsonrpc: "2.0", id, method, params })
+            .map_err(|v| Error::Serialisation(v.to_string()))?;
+        trace!("[{}] -> {}", plugin_id, line);
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+
+        loop {
+            let mut line = String::new();
+            let read = match tokio::time::timeout(timeout, self.stdout.read_line(&mut line)).await {
+                Ok(read) => read?,
+                Err(_) => {
+                    let _ = self.child.kill().await;
+                    return Err(Error::Generic(format!("plugin {} did not respond to {} within {:?}", plugin_id, method, timeout)))
+                }
+            };
+            if read == 0 {
+                return Err(Error::Generic(format!("plugin {} exited unexpectedly", plugin_id)))
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                continue
+            }
+            trace!("[{}] <- {}", plugin_id, line);
+            let message: Message = serde_json::from_str(line)
+                .map_err(|v| Error::Deserialisation(format!("invalid message from plugin {}: {}", plugin_id, v)))?;
+
+            match (message.id, message.method.as_deref()) {
+                (Some(response_id), _) if response_id == id => {
+                    if let Some(error) = message.error {
+                        return Err(error.into_error(plugin_id))
+                    }
+                    return serde_json::from_value(message.result)
+                        .map_err(|v| Error::Deserialisation(format!("invalid {} result from plugin {}: {}", method, plugin_id, v)))
+                },
+                (None, Some("log")) => {
+                    let params: LogParams = match serde_json::from_value(message.params) {
+                        Ok(v) => v,
+                        Err(_) => continue
+                    };
+                    match params.level.as_str() {
+                        "error" => { error!("[{}] {}", plugin_id, params.message); }
+                        "warning" => { warning!("[{}] {}", plugin_id, params.message); }
+                        "info" => { info!("[{}] {}", plugin_id, params.message); }
+                        "debug" => { debug!("[{}] {}", plugin_id, params.message); }
+                        _ => { trace!("[{}] {}", plugin_id, params.message); }
+                    }
+                },
+                _ => { trace!("[{}] Ignoring unexpected message", plugin_id); }
+            }
+        }
+    }
+}
+
+/// A source backed by an external executable speaking the fontpm plugin protocol (see `PROTOCOL.md`).
+///
+/// The process is started on the first request and kept alive until the source is dropped.
+pub struct ProcessSource<'host> {
+    id: String,
+    executable: PathBuf,
+    host: &'host dyn FpmHost,
+    name: OnceLock<String>,
+    timeout: Duration,
+    connection: Mutex<Option<Connection>>
+}
+
+impl<'host> ProcessSource<'host> {
+    pub fn new(executable: PluginExecutable) -> Self {
+        ProcessSource {
+            id: executable.id,
+            executable: executable.path,
+            host: &EmptyFpmHost::EMPTY_HOST,
+            name: OnceLock::new(),
+            timeout: DEFAULT_TIMEOUT,
+            connection: Mutex::new(None)
+        }
+    }
+    /// Sets how long the plugin may go without sending a message before it's killed. See [`DEFAULT_TIMEOUT`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn call<P, R>(&self, method: &str, params: P) -> Result<R, Error> where P: Serialize, R: DeserializeOwned {
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            let mut new = Connection::spawn(&self.executable)?;
+            let config = match self.host.config(self.id.clone()) {
+                Some(config) => Some(serde_json::to_value(config).map_err(|v| Error::Serialisation(v.to_string()))?),
+                None => None
+            };
+            let described: DescribeResult = new.call(&self.id, "describe", DescribeParams {
+                protocol_version: PROTOCOL_VERSION,
+                fontpm_version: self.host.version().to_string(),
+                user_agent: self.host.user_agent(),
+                cache_dir: self.host.cache_dir_for(&self.id),
+                config
+            }, self.timeout).await?;
+            if described.protocol_version != PROTOCOL_VERSION {
+                return Err(Error::Generic(format!(
+                    "plugin {} speaks protocol version {}, but this version of fontpm only supports version {}",
+                    self.id, described.protocol_version, PROTOCOL_VERSION
+                )))
+            }
+            let _ = self.name.set(described.name);
+            *connection = Some(new);
+        }
+
+        let current = connection.as_mut().unwrap();
+        let result = current.call(&self.id, method, params, self.timeout).await;
+        if matches!(result, Err(Error::IO(_))) || current.exited() {
+            // the process is gone (or was killed), so start a new one next time
+            *connection = None;
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<'host> Source<'host> for ProcessSource<'host> {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    /// The name reported by the plugin, or the ID if the plugin hasn't been started yet.
+    fn name(&self) -> &str {
+        self.name.get().unwrap_or(&self.id)
+    }
+
+    fn set_host(&mut self, host: &'host dyn FpmHost) {
+        self.host = host;
+    }
+
+    async fn refresh(&self, force: bool) -> Result<RefreshOutput, Error> {
+        let result: RefreshResult = self.call("refresh", RefreshParams { force }).await?;
+        Ok(result.into())
+    }
+
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+        let result: ResolveResult = self.call("resolve", Spec::from(spec)).await?;
+        Ok((result.spec.try_into()?, result.description.into()))
+    }
+
+    async fn download_font(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let result: DownloadResult = self.call("download", DownloadParams {
+            spec: spec.into(),
+            dir: dir.clone()
+        }).await?;
+
+        let mut paths = HashMap::new();
+        for file in result.files {
+            paths.insert(file.variant.try_into()?, file.path);
+        }
+        Ok(paths)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let result: SearchResult = self.call("search", SearchParams { query }).await?;
+        Ok(result.fonts.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,240 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription, FontInstallSpec, FontStyle, FontVariantSpec, FontWeight};
+use fontpm_api::source::RefreshOutput;
+
+/// The version of the protocol spoken by this implementation.
+/// Plugins must report the same version in their `describe` response.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Error code plugins use to signal that a family does not exist.
+pub const NO_SUCH_FAMILY: i64 = 1;
+
+#[derive(Serialize)]
+pub struct Request<'a, P> {
+    pub jsonrpc: &'static str,
+    pub id: u64,
+    pub method: &'a str,
+    pub params: P
+}
+
+/// Any message sent by a plugin - either a response or a notification.
+#[derive(Deserialize)]
+pub struct Message {
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default)]
+    pub result: Value,
+    #[serde(default)]
+    pub error: Option<ErrorObject>
+}
+
+#[derive(Deserialize)]
+pub struct ErrorObject {
+    pub code: i64,
+    pub message: String
+}
+impl ErrorObject {
+    pub fn into_error(self, plugin_id: &str) -> Error {
+        match self.code {
+            NO_SUCH_FAMILY => Error::NoSuchFamily(self.message),
+            code => Error::Generic(format!("plugin {} returned error {}: {}", plugin_id, code, self.message))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LogParams {
+    pub level: String,
+    pub message: String
+}
+
+#[derive(Serialize)]
+pub struct DescribeParams {
+    pub protocol_version: u32,
+    pub fontpm_version: String,
+    pub user_agent: String,
+    pub cache_dir: PathBuf,
+    pub config: Option<Value>
+}
+#[derive(Deserialize)]
+pub struct DescribeResult {
+    pub protocol_version: u32,
+    pub name: String
+}
+
+#[derive(Serialize)]
+pub struct RefreshParams {
+    pub force: bool
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefreshStatus {
+    Downloaded,
+    AlreadyUpToDate
+}
+#[derive(Deserialize)]
+pub struct RefreshResult {
+    pub status: RefreshStatus
+}
+impl From<RefreshResult> for RefreshOutput {
+    fn from(value: RefreshResult) -> Self {
+        match value.status {
+            RefreshStatus::Downloaded => RefreshOutput::Downloaded,
+            RefreshStatus::AlreadyUpToDate => RefreshOutput::AlreadyUpToDate
+        }
+    }
+}
+
+/// A weight is either a number or one of `"variable"`, `"all-fixed"` and `"all"`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Weight {
+    Fixed(u32),
+    Named(String)
+}
+impl From<FontWeight> for Weight {
+    fn from(value: FontWeight) -> Self {
+        match value {
+            FontWeight::Defined(defined) => defined.into(),
+            FontWeight::AllFixed => Weight::Named("all-fixed".into()),
+            FontWeight::All => Weight::Named("all".into())
+        }
+    }
+}
+impl From<DefinedFontWeight> for Weight {
+    fn from(value: DefinedFontWeight) -> Self {
+        match value {
+            DefinedFontWeight::Fixed(weight) => Weight::Fixed(weight),
+            DefinedFontWeight::Variable => Weight::Named("variable".into())
+        }
+    }
+}
+impl TryFrom<Weight> for DefinedFontWeight {
+    type Error = Error;
+
+    fn try_from(value: Weight) -> Result<Self, Self::Error> {
+        match value {
+            Weight::Fixed(weight) => Ok(DefinedFontWeight::Fixed(weight)),
+            Weight::Named(name) if name == "variable" => Ok(DefinedFontWeight::Variable),
+            Weight::Named(name) => Err(Error::Deserialisation(format!("Not a defined font weight: {}", name)))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Variant {
+    pub weight: Weight,
+    /// One of `"regular"`, `"italic"` and (in requests only) `"all"`.
+    pub style: String
+}
+impl From<&FontVariantSpec> for Variant {
+    fn from(value: &FontVariantSpec) -> Self {
+        Variant {
+            weight: value.weight.into(),
+            style: match value.style {
+                FontStyle::Defined(style) => style.to_string(),
+                FontStyle::All => "all".into()
+            }
+        }
+    }
+}
+impl From<&DefinedFontVariantSpec> for Variant {
+    fn from(value: &DefinedFontVariantSpec) -> Self {
+        Variant {
+            weight: value.weight.into(),
+            style: value.style.to_string()
+        }
+    }
+}
+impl TryFrom<Variant> for DefinedFontVariantSpec {
+    type Error = Error;
+
+    fn try_from(value: Variant) -> Result<Self, Self::Error> {
+        Ok(DefinedFontVariantSpec {
+            weight: value.weight.try_into()?,
+            style: value.style.parse::<DefinedFontStyle>()?
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Spec {
+    pub id: String,
+    pub styles: Vec<Variant>
+}
+impl From<&FontInstallSpec> for Spec {
+    fn from(value: &FontInstallSpec) -> Self {
+        Spec {
+            id: value.id.clone(),
+            styles: value.styles.iter().map(Into::into).collect()
+        }
+    }
+}
+impl From<&DefinedFontInstallSpec> for Spec {
+    fn from(value: &DefinedFontInstallSpec) -> Self {
+        Spec {
+            id: value.id.clone(),
+            styles: value.styles.iter().map(Into::into).collect()
+        }
+    }
+}
+impl TryFrom<Spec> for DefinedFontInstallSpec {
+    type Error = Error;
+
+    fn try_from(value: Spec) -> Result<Self, Self::Error> {
+        let styles: Result<Vec<DefinedFontVariantSpec>, Error> = value.styles.into_iter()
+            .map(TryInto::try_into)
+            .collect();
+        Ok(DefinedFontInstallSpec::new(value.id, styles?))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Description {
+    pub name: String,
+    pub id: String,
+    pub version: String
+}
+impl From<Description> for FontDescription {
+    fn from(value: Description) -> Self {
+        FontDescription::new(value.name, value.id, value.version)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ResolveResult {
+    pub spec: Spec,
+    pub description: Description
+}
+
+#[derive(Serialize)]
+pub struct DownloadParams {
+    pub spec: Spec,
+    pub dir: PathBuf
+}
+#[derive(Deserialize)]
+pub struct DownloadedFile {
+    #[serde(flatten)]
+    pub variant: Variant,
+    pub path: PathBuf
+}
+#[derive(Deserialize)]
+pub struct DownloadResult {
+    pub files: Vec<DownloadedFile>
+}
+
+#[derive(Serialize)]
+pub struct SearchParams<'a> {
+    pub query: &'a str
+}
+#[derive(Deserialize)]
+pub struct SearchResult {
+    pub fonts: Vec<Description>
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontInstallSpec};
+use fontpm_api::source::RefreshOutput;
+use fontpm_source_process::{discover, find, ProcessSource};
+use fontpm_testing::layout::assert_file_contents;
+use fontpm_testing::TempFpmHost;
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+}
+
+#[test]
+fn discovers_stub() {
+    let found = discover([fixtures()]);
+    let stub = found.iter().find(|v| v.id == "stub").expect("stub plugin should be discovered");
+    assert_eq!(stub.path, fixtures().join("fontpm-source-stub"));
+    assert!(find("definitely-not-a-plugin", [fixtures()]).is_none());
+}
+
+#[tokio::test]
+async fn stub_source() {
+    let host = TempFpmHost::new();
+
+    let mut source = ProcessSource::new(find("stub", [fixtures()]).unwrap());
+    source.set_host(&host);
+    assert_eq!(source.name(), "stub");
+
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert_eq!(source.name(), "Stub Source");
+
+    let (spec, description) = source.resolve_font(&FontInstallSpec::new_all_styles("stub-sans")).await.unwrap();
+    assert_eq!(description.name, "Stub Sans");
+    let bold_italic = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Italic };
+    assert_eq!(spec.styles, vec![DefinedFontVariantSpec::REGULAR, bold_italic]);
+
+    match source.resolve_font(&FontInstallSpec::new_all_styles("nope")).await {
+        Err(Error::NoSuchFamily(id)) => assert_eq!(id, "nope"),
+        _ => panic!("expected NoSuchFamily")
+    }
+
+    let files = source.download_font(&spec, &host.cache_dir_for("stub")).await.unwrap();
+    assert_eq!(files.len(), 2);
+    assert_file_contents(&files[&bold_italic], "bold italic");
+
+    let found = source.search("stub").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, "stub-sans");
+}
+
+#[tokio::test]
+async fn unresponsive_plugins_time_out() {
+    let host = TempFpmHost::new();
+
+    let mut source = ProcessSource::new(find("hang", [fixtures()]).unwrap()).with_timeout(Duration::from_millis(200));
+    source.set_host(&host);
+
+    let start = Instant::now();
+    match source.refresh(false).await {
+        Err(Error::Generic(message)) => assert!(message.contains("did not respond"), "unexpected error: {}", message),
+        _ => panic!("expected the plugin to time out")
+    }
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use reqwest::Url;
+use serde::Deserialize;
+use fontpm_api::Error;
+use fontpm_api::host::toml;
+
+/// Configuration from `[sources.<id>]`, for a source whose `type` is `registry`.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct RegistryConfig {
+    /// A URL or path of the index.
+    index: Option<String>,
+    /// The name shown for the source (defaults to its ID).
+    name: Option<String>
+}
+
+impl RegistryConfig {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, Error> {
+        value.clone().try_into::<Self>()
+            .map_err(|v| Error::Deserialisation(format!("invalid registry configuration: {}", v)))
+    }
+
+    pub fn index(&self) -> Option<Result<Location, Error>> {
+        self.index.as_deref().map(Location::parse)
+    }
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// Where the index or a file is: either a URL or a path on this computer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Location {
+    Url(Url),
+    Path(PathBuf)
+}
+
+impl Location {
+    /// Reads anything with a scheme (like `https://`) as a URL, and anything else as a path.
+    /// `file://` URLs are paths.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        if !value.contains("://") {
+            return Ok(Location::Path(PathBuf::from(value)))
+        }
+        let url = Url::parse(value).map_err(|e| Error::Generic(format!("invalid URL {}: {}", value, e)))?;
+        if url.scheme() == "file" {
+            url.to_file_path()
+                .map(Location::Path)
+                .map_err(|_| Error::Generic(format!("invalid file URL {}", value)))
+        } else {
+            Ok(Location::Url(url))
+        }
+    }
+
+    /// The location of `path`, which is either a URL or path of its own, or relative to this location.
+    /// An index fetched from a URL can only refer to other URLs, so that it can't make fontpm copy files on this computer.
+    pub fn join(&self, path: &str) -> Result<Self, Error> {
+        if path.contains("://") {
+            return match (self, Self::parse(path)?) {
+                (Location::Url(_), Location::Path(_)) => Err(Error::Generic(format!("{} is a local file, but the index is not", path))),
+                (_, location) => Ok(location)
+            }
+        }
+        match self {
+            Location::Url(url) => url.join(path)
+                .map(Location::Url)
+                .map_err(|e| Error::Generic(format!("invalid path {}: {}", path, e))),
+            Location::Path(index) => Ok(Location::Path(index.parent().map_or_else(|| PathBuf::from(path), |v| v.join(path))))
+        }
+    }
+
+    /// The extension of the file, including the dot, or an empty string if it has none.
+    pub fn extension(&self) -> String {
+        let path = match self {
+            Location::Url(url) => PathBuf::from(url.path()),
+            Location::Path(path) => path.clone()
+        };
+        path.extension().map_or(String::new(), |v| format!(".{}", v.to_string_lossy()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let url = Location::parse("https://fonts.example.com/registry/index.json").unwrap();
+        assert_eq!(url.join("acme/AcmeSans.woff2").unwrap(), Location::parse("https://fonts.example.com/registry/acme/AcmeSans.woff2").unwrap());
+        assert_eq!(url.join("https://cdn.example.com/a.ttf").unwrap(), Location::Url(Url::parse("https://cdn.example.com/a.ttf").unwrap()));
+        assert_eq!(url.extension(), ".json");
+        assert!(url.join("file:///etc/passwd").is_err());
+        assert_eq!(url.join("/etc/passwd").unwrap(), Location::parse("https://fonts.example.com/etc/passwd").unwrap());
+
+        let path = Location::parse("/srv/fonts/index.toml").unwrap();
+        assert_eq!(path.join("acme/AcmeSans.ttf").unwrap(), Location::Path(PathBuf::from("/srv/fonts/acme/AcmeSans.ttf")));
+        assert_eq!(Location::parse("file:///srv/fonts/index.toml").unwrap(), path);
+        assert_eq!(path.extension(), ".toml");
+        assert_eq!(path.join("file:///srv/other/a.ttf").unwrap(), Location::Path(PathBuf::from("/srv/other/a.ttf")));
+    }
+}
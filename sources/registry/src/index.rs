@@ -0,0 +1,166 @@
+//! The index a registry is described by, whose format is documented in `INDEX.md`.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use serde::{Deserialize, Serialize};
+use fontpm_api::Error;
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight};
+use fontpm_api::host::toml;
+use fontpm_api::integrity::FileIntegrity;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Index {
+    pub families: BTreeMap<String, Family>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Family {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The font files, by variant (e.g. `regular`, `700italic` or `variable`).
+    pub variants: BTreeMap<String, File>,
+    /// The license documents, by the file name they're installed as (e.g. `LICENSE.txt`).
+    #[serde(default)]
+    pub licenses: BTreeMap<String, File>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct File {
+    /// A URL, or a path relative to the index.
+    pub path: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>
+}
+
+impl File {
+    pub fn integrity(&self) -> FileIntegrity {
+        FileIntegrity::new(self.size, self.sha256.clone())
+    }
+}
+
+impl Family {
+    /// The files of every variant, keyed by the variants they are.
+    pub fn files(&self) -> Result<BTreeMap<DefinedFontVariantSpec, &File>, Error> {
+        self.variants.iter()
+            .map(|(variant, file)| Ok((parse_variant(variant)?, file)))
+            .collect()
+    }
+
+    /// The total size of the files of every variant, if all of them are known.
+    pub fn size(&self) -> Option<u64> {
+        self.variants.values().map(|v| v.size).sum()
+    }
+
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.iter().any(|v| v.eq_ignore_ascii_case(tag)))
+    }
+}
+
+impl Index {
+    /// Reads an index in TOML if `is_toml` is true, or in JSON otherwise, and checks that every family can be installed.
+    pub fn parse(data: &[u8], is_toml: bool) -> Result<Self, Error> {
+        let index: Index = if is_toml {
+            let data = std::str::from_utf8(data).map_err(|e| Error::Deserialisation(e.to_string()))?;
+            toml::from_str(data).map_err(|e| Error::Deserialisation(e.to_string()))?
+        } else {
+            serde_json::from_slice(data).map_err(|e| Error::Deserialisation(e.to_string()))?
+        };
+
+        for (id, family) in &index.families {
+            // the ID, version and license names all end up in the paths files are saved to
+            check_relative(id)
+                .map_err(|e| Error::Deserialisation(format!("invalid family ID: {}", e)))?;
+            if family.variants.is_empty() {
+                return Err(Error::Deserialisation(format!("family {} has no variants", id)))
+            }
+            family.files().map_err(|e| Error::Deserialisation(format!("family {}: {}", id, e)))?;
+            check_relative(&family.version)
+                .map_err(|e| Error::Deserialisation(format!("family {} has an invalid version: {}", id, e)))?;
+            for name in family.licenses.keys() {
+                check_relative(name)
+                    .map_err(|e| Error::Deserialisation(format!("family {} has an invalid license name: {}", id, e)))?;
+            }
+        }
+        Ok(index)
+    }
+}
+
+/// Checks that `path` is a relative path that stays inside the directory it's joined to (no `..`, `/` or drive prefixes).
+fn check_relative(path: &str) -> Result<(), Error> {
+    if path.is_empty() || !Path::new(path).components().all(|v| matches!(v, Component::Normal(_))) {
+        return Err(Error::Deserialisation(format!("{} is not a plain relative path", path)))
+    }
+    Ok(())
+}
+
+/// Reads a variant name: `regular`, `italic`, a weight optionally followed by `italic` (e.g. `700italic`),
+/// `variable` or `variableitalic`.
+pub fn parse_variant(name: &str) -> Result<DefinedFontVariantSpec, Error> {
+    let (weight, style) = match name.strip_suffix("italic") {
+        Some(weight) => (weight, DefinedFontStyle::Italic),
+        None => (name, DefinedFontStyle::Regular)
+    };
+    let weight = match weight {
+        "" if style == DefinedFontStyle::Italic => DefinedFontWeight::REGULAR,
+        "regular" if style == DefinedFontStyle::Regular => DefinedFontWeight::REGULAR,
+        "variable" => DefinedFontWeight::Variable,
+        _ => match weight.parse() {
+            Ok(weight @ 1..=1000) => DefinedFontWeight::Fixed(weight),
+            _ => return Err(Error::Deserialisation(format!("invalid variant {}", name)))
+        }
+    };
+    Ok(DefinedFontVariantSpec { weight, style })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants() {
+        let variant = |weight, style| DefinedFontVariantSpec { weight, style };
+        assert_eq!(parse_variant("regular").unwrap(), DefinedFontVariantSpec::REGULAR);
+        assert_eq!(parse_variant("italic").unwrap(), variant(DefinedFontWeight::REGULAR, DefinedFontStyle::Italic));
+        assert_eq!(parse_variant("700").unwrap(), variant(DefinedFontWeight::Fixed(700), DefinedFontStyle::Regular));
+        assert_eq!(parse_variant("300italic").unwrap(), variant(DefinedFontWeight::Fixed(300), DefinedFontStyle::Italic));
+        assert_eq!(parse_variant("variableitalic").unwrap(), variant(DefinedFontWeight::Variable, DefinedFontStyle::Italic));
+        assert!(parse_variant("bold").is_err());
+        assert!(parse_variant("regularitalic").is_err());
+        assert!(parse_variant("0").is_err());
+    }
+
+    #[test]
+    fn json_and_toml() {
+        let json = Index::parse(include_bytes!("../tests/fixtures/index.json"), false).unwrap();
+        let toml = Index::parse(include_bytes!("../tests/fixtures/index.toml"), true).unwrap();
+        assert_eq!(json, toml);
+        assert_eq!(json.families["acme-sans"].files().unwrap().len(), 3);
+
+        assert!(Index::parse(br#"{"families": {"acme-sans": {"name": "ACME Sans", "version": "1", "variants": {}}}}"#, false).is_err());
+        assert!(Index::parse(br#"{"families": {"acme-sans": {"name": "ACME Sans", "version": "1", "variants": {"bold": {"path": "a.ttf"}}}}}"#, false).is_err());
+    }
+
+    #[test]
+    fn paths_stay_in_the_cache() {
+        let family = |id: &str, version: &str, license: &str| Index::parse(format!(
+            r#"{{"families": {{{:?}: {{"name": "ACME Sans", "version": {:?}, "variants": {{"regular": {{"path": "a.ttf"}}}}, "licenses": {{{:?}: {{"path": "LICENSE"}}}}}}}}}}"#,
+            id, version, license
+        ).as_bytes(), false);
+        let index = |version: &str, license: &str| family("acme-sans", version, license);
+        assert!(index("2.100", "LICENSE.txt").is_ok());
+        assert!(index("2.100", "licenses/OFL.txt").is_ok());
+        assert!(index("../../x", "LICENSE.txt").is_err());
+        assert!(index("/tmp", "LICENSE.txt").is_err());
+        assert!(index("", "LICENSE.txt").is_err());
+        assert!(index("2.100", "../../.bashrc").is_err());
+        assert!(index("2.100", "/etc/profile").is_err());
+        assert!(index("2.100", "licenses/./OFL.txt").is_ok());
+        assert!(family("../../x", "2.100", "LICENSE.txt").is_err());
+        assert!(family("/tmp/x", "2.100", "LICENSE.txt").is_err());
+        assert!(family("", "2.100", "LICENSE.txt").is_err());
+    }
+}
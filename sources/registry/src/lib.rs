@@ -0,0 +1,277 @@
+//! A source reading a custom index of fonts (see `INDEX.md`) from a file or a URL,
+//! which can be set up any number of times with `[sources.<id>]` sections whose `type` is `registry`.
+
+mod config;
+mod index;
+
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use reqwest::{Client, ClientBuilder};
+use fontpm_api::{FpmHost, Source, trace, warning};
+use fontpm_api::async_trait::async_trait;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontVariantSpec, FontDescription, FontInstallSpec, FontVariantSpec};
+use fontpm_api::host::EmptyFpmHost;
+use fontpm_api::registry::SourceType;
+use fontpm_api::source::{RefreshOutput, TaggedFont};
+use fontpm_api::util::create_parent;
+use fontpm_api::Error;
+use crate::config::{Location, RegistryConfig};
+use crate::index::{Family, File, Index};
+
+/// The last index that was fetched, as JSON whatever format the registry uses.
+const INDEX_FILE: &str = "index.json";
+
+pub struct RegistrySource<'host> {
+    id: String,
+    name: String,
+    host: &'host dyn FpmHost,
+    client: Option<Client>,
+    config: RegistryConfig,
+    /// The index, once it has been loaded.
+    index: RwLock<Option<Arc<Index>>>
+}
+
+fn create_source<'host>(id: &str, host: &'host dyn FpmHost) -> Box<dyn Source<'host> + 'host> {
+    let mut source = RegistrySource::new(id);
+    source.set_host(host);
+    Box::new(source)
+}
+fontpm_api::register_source_type!(SourceType::new(RegistrySource::TYPE, create_source));
+
+fn description(id: &str, family: &Family) -> FontDescription {
+    FontDescription::new(&family.name, id, &family.version)
+}
+
+impl<'host> RegistrySource<'host> {
+    /// The `type` of the sources this creates.
+    pub const TYPE: &'static str = "registry";
+
+    pub fn new(id: impl ToString) -> Self {
+        let id = id.to_string();
+        RegistrySource {
+            name: id.clone(),
+            id,
+            host: &EmptyFpmHost::EMPTY_HOST,
+            client: None,
+            config: RegistryConfig::default(),
+            index: RwLock::new(None)
+        }
+    }
+
+    fn client(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+
+    fn index_file(&self) -> PathBuf {
+        self.host.cache_dir_for(&self.id).join(INDEX_FILE)
+    }
+
+    fn index_location(&self) -> Result<Location, Error> {
+        self.config.index()
+            .unwrap_or_else(|| Err(Error::Generic(format!("no index is configured - set `index` in [sources.{}]", self.id))))
+    }
+
+    async fn fetch(&self, location: &Location) -> Result<Vec<u8>, Error> {
+        match location {
+            Location::Url(url) => {
+                trace!("[{}] Downloading {}", self.id, url);
+                let response = self.client().get(url.clone()).send().await?.error_for_status()?;
+                Ok(response.bytes().await?.to_vec())
+            },
+            Location::Path(path) => std::fs::read(path)
+                .map_err(|e| Error::Generic(format!("could not read {}: {}", path.display(), e)))
+        }
+    }
+
+    /// Fetches the index and saves it, returning whether it changed since it was last fetched.
+    async fn fetch_index(&self) -> Result<bool, Error> {
+        let location = self.index_location()?;
+        let index = Index::parse(&self.fetch(&location).await?, location.extension() == ".toml")
+            .map_err(|e| Error::Deserialisation(format!("the index of {} is invalid: {}", self.id, e)))?;
+        let changed = self.saved_index()?.as_ref() != Some(&index);
+        self.save_index(index)?;
+        Ok(changed)
+    }
+
+    /// Saves `index`, and uses it from now on.
+    fn save_index(&self, index: Index) -> Result<(), Error> {
+        let path = self.index_file();
+        create_parent(&path)?;
+        let json = serde_json::to_vec(&index).map_err(|e| Error::Serialisation(e.to_string()))?;
+        std::fs::write(path, json)?;
+        *self.index.write().unwrap() = Some(Arc::new(index));
+        Ok(())
+    }
+
+    /// The index that was last fetched, if there is one.
+    fn saved_index(&self) -> Result<Option<Index>, Error> {
+        let path = self.index_file();
+        if !path.exists() {
+            return Ok(None)
+        }
+        match serde_json::from_slice(&std::fs::read(&path)?) {
+            Ok(index) => Ok(Some(index)),
+            Err(e) => {
+                trace!("[{}] Fetching the index again, as {} is invalid: {}", self.id, INDEX_FILE, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// The index, which is loaded the first time it's needed, and fetched if it never has been.
+    async fn index(&self) -> Result<Arc<Index>, Error> {
+        if let Some(index) = self.index.read().unwrap().as_ref() {
+            return Ok(index.clone())
+        }
+        match self.saved_index()? {
+            Some(index) => *self.index.write().unwrap() = Some(Arc::new(index)),
+            None => {
+                self.fetch_index().await?;
+            }
+        }
+        Ok(self.index.read().unwrap().clone().expect("the index was just loaded"))
+    }
+
+    async fn family(&self, id: &str) -> Result<Family, Error> {
+        self.index().await?.families.get(id)
+            .cloned()
+            .ok_or_else(|| Error::NoSuchFamily(id.to_string()))
+    }
+
+    fn location_of(&self, file: &File) -> Result<Location, Error> {
+        self.index_location()?.join(&file.path)
+    }
+
+    /// Fetches `file` from `location` to `target`, unless it already has been.
+    /// Both fetched and cached files are checked against what the index says about them.
+    async fn fetch_cached(&self, file: &File, location: &Location, target: PathBuf) -> Result<PathBuf, Error> {
+        let integrity = file.integrity();
+
+        if target.exists() {
+            match integrity.verify(&target) {
+                Ok(()) => return Ok(target),
+                Err(e) => {
+                    warning!("[{}] Downloading {} again: {}", self.id, file.path, e);
+                }
+            }
+        }
+
+        let data = self.fetch(location).await?;
+        integrity.verify_bytes(&file.path, &data)?;
+        target.parent().map(create_dir_all).transpose()?;
+        // the file is only moved into place once it's complete, so a failed write never leaves a partial file that looks cached
+        let part = PathBuf::from(format!("{}.part", target.display()));
+        std::fs::write(&part, data)?;
+        std::fs::rename(&part, &target)?;
+        Ok(target)
+    }
+}
+
+#[async_trait]
+impl<'host> Source<'host> for RegistrySource<'host> {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_host(&mut self, host: &'host dyn FpmHost) {
+        self.host = host;
+        self.index = RwLock::new(None);
+        self.config = match host.config(self.id.clone()).map(RegistryConfig::from_toml) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                warning!("[{}] {} - using the default configuration", self.id, e);
+                RegistryConfig::default()
+            },
+            None => RegistryConfig::default()
+        };
+        self.name = self.config.name().unwrap_or(&self.id).to_string();
+        self.client = Some(
+            ClientBuilder::new()
+                .user_agent(host.user_agent())
+                .build()
+                .expect("HTTP client required")
+        )
+    }
+
+    async fn refresh(&self, _force_refresh: bool) -> Result<RefreshOutput, Error> {
+        // the index is small enough that it's always fetched again
+        if self.fetch_index().await? {
+            Ok(RefreshOutput::Downloaded)
+        } else {
+            Ok(RefreshOutput::AlreadyUpToDate)
+        }
+    }
+
+    async fn import_index(&self, index: &[u8]) -> Result<(), Error> {
+        // an imported index is read in the same format as the configured one
+        let is_toml = self.index_location().is_ok_and(|v| v.extension() == ".toml");
+        let index = Index::parse(index, is_toml)
+            .map_err(|e| Error::Deserialisation(format!("not a valid index: {}", e)))?;
+        self.save_index(index)
+    }
+
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+        if !spec.subsets.is_empty() {
+            warning!("[{}] {} has no subsets, so it will be installed in full", self.id, spec.id);
+        }
+        let family = self.family(&spec.id).await?;
+
+        let styles: Vec<DefinedFontVariantSpec> = family.files()?.into_keys()
+            .filter(|available| {
+                let available = FontVariantSpec::from(*available);
+                spec.styles.iter().any(|requested| available.is_covered_by(requested))
+            })
+            .collect();
+
+        Ok((DefinedFontInstallSpec::new(&spec.id, styles), description(&spec.id, &family)))
+    }
+
+    async fn download_font(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let family = self.family(&spec.id).await?;
+        let files = family.files()?;
+        // the version is part of the path, so that a new version of a file is fetched even if the index doesn't have its digest
+        let dir = dir.join(&spec.id).join(&family.version);
+
+        let mut paths = HashMap::new();
+        for variant in &spec.styles {
+            let file = files.get(variant)
+                .ok_or_else(|| Error::Generic(format!("{} has no file for the variant {} {}", spec.id, variant.weight, variant.style)))?;
+            let location = self.location_of(file)?;
+            let target = dir.join(format!("{}-{}{}", variant.weight, variant.style, location.extension()));
+            paths.insert(*variant, self.fetch_cached(file, &location, target).await?);
+        }
+        Ok(paths)
+    }
+
+    async fn download_licenses(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<String, PathBuf>, Error> {
+        let family = self.family(&spec.id).await?;
+        let dir = dir.join(&spec.id).join(&family.version).join("licenses");
+
+        let mut paths = HashMap::new();
+        for (name, file) in &family.licenses {
+            let path = self.fetch_cached(file, &self.location_of(file)?, dir.join(name)).await?;
+            paths.insert(name.clone(), path);
+        }
+        Ok(paths)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let query = query.to_lowercase();
+        Ok(self.index().await?.families.iter()
+            .filter(|(id, family)| id.contains(&query) || family.name.to_lowercase().contains(&query))
+            .map(|(id, family)| description(id, family))
+            .collect())
+    }
+
+    async fn search_by_tags(&self, tags: &[String]) -> Result<Vec<TaggedFont>, Error> {
+        Ok(self.index().await?.families.iter()
+            .filter(|(_, family)| family.has_tags(tags))
+            .map(|(id, family)| TaggedFont { font: description(id, family), size: family.size() })
+            .collect())
+    }
+}
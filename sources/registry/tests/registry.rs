@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::Path;
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::font::{DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontInstallSpec};
+use fontpm_api::host::toml;
+use fontpm_api::registry;
+use fontpm_api::source::RefreshOutput;
+use fontpm_source_registry::RegistrySource;
+use fontpm_testing::{FixtureResponse, FixtureServer, TempFpmHost};
+use fontpm_testing::layout::{assert_file_contents, installed_files};
+
+const INDEX_JSON: &str = include_str!("fixtures/index.json");
+const INDEX_TOML: &str = include_str!("fixtures/index.toml");
+/// The files in the fixture indices, relative to them.
+const FILES: &[&str] = &[
+    "acme-sans/AcmeSans-Regular.ttf",
+    "acme-sans/AcmeSans-Bold.ttf",
+    "acme-sans/AcmeSans-Italic.ttf",
+    "acme-mono/AcmeMono[wght].ttf",
+    "licenses/acme-eula.txt"
+];
+const BOLD: DefinedFontVariantSpec = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(700), style: DefinedFontStyle::Regular };
+
+/// Writes the JSON fixture index to `dir`, along with every file in it (each file containing its own path).
+fn write_registry(dir: &Path) {
+    for file in FILES {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, file).unwrap();
+    }
+    fs::write(dir.join("index.json"), INDEX_JSON).unwrap();
+}
+
+/// Serves the TOML fixture index at `/registry/index.toml`, along with every file in it.
+fn serve_registry() -> FixtureServer {
+    let server = FixtureServer::start();
+    server.route("/registry/index.toml", FixtureResponse::ok(INDEX_TOML));
+    for file in FILES {
+        // the index has paths, which are relative URLs
+        server.route(format!("/registry/{}", file), FixtureResponse::ok(*file));
+    }
+    server
+}
+
+fn configure(host: &mut TempFpmHost, id: &str, config: &str) {
+    host.set_config(id, toml::from_str(&format!("type = \"registry\"\n{}", config)).unwrap());
+}
+
+#[tokio::test]
+async fn file_backed_registry() {
+    let mut host = TempFpmHost::new();
+    let dir = host.path().join("acme");
+    write_registry(&dir);
+    configure(&mut host, "acme", &format!("index = \"{}\"\nname = \"ACME Type\"", dir.join("index.json").display()));
+
+    let source = registry::create_source("acme", &host).unwrap().unwrap();
+    assert_eq!(source.id(), "acme");
+    assert_eq!(source.name(), "ACME Type");
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::Downloaded);
+    assert!(source.refresh(false).await.unwrap() == RefreshOutput::AlreadyUpToDate);
+
+    let (spec, description) = source.resolve_font(&FontInstallSpec::new_all_styles("acme-sans")).await.unwrap();
+    assert_eq!(description.name, "ACME Sans");
+    assert_eq!(description.version, "2.100");
+    assert_eq!(spec.styles.len(), 3);
+
+    let cache_dir = host.cache_dir_for(source.id());
+    let paths = source.download_font(&spec, &cache_dir).await.unwrap();
+    assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], FILES[0]);
+    assert_file_contents(&paths[&BOLD], FILES[1]);
+    assert!(paths[&BOLD].starts_with(&cache_dir));
+    assert_eq!(paths[&BOLD].extension().unwrap(), "ttf");
+
+    let licenses = source.download_licenses(&spec, &cache_dir).await.unwrap();
+    assert_file_contents(&licenses["LICENSE.txt"], FILES[4]);
+
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("acme-mono")).await.unwrap();
+    assert!(source.download_licenses(&spec, &cache_dir).await.unwrap().is_empty());
+
+    let tagged = source.search_by_tags(&["Brand".to_string()]).await.unwrap();
+    let tagged: Vec<_> = tagged.iter().map(|v| (v.font.id.as_str(), v.size)).collect();
+    assert_eq!(tagged, vec![("acme-mono", None), ("acme-sans", Some(86))]);
+    assert_eq!(source.search("sans").await.unwrap().len(), 1);
+
+    match source.resolve_font(&FontInstallSpec::new_all_styles("acme-serif")).await {
+        Err(Error::NoSuchFamily(id)) => assert_eq!(id, "acme-serif"),
+        other => panic!("expected a missing family, got {:?}", other.map(|v| v.1))
+    }
+}
+
+#[tokio::test]
+async fn several_registries() {
+    let server = serve_registry();
+    let mut host = TempFpmHost::new();
+    let dir = host.path().join("acme");
+    write_registry(&dir);
+    configure(&mut host, "acme", &format!("index = \"{}\"", dir.join("index.json").display()));
+    configure(&mut host, "acme-web", &format!("index = \"{}\"", server.url_for("/registry/index.toml")));
+
+    let local = registry::create_source("acme", &host).unwrap().unwrap();
+    let web = registry::create_source("acme-web", &host).unwrap().unwrap();
+    assert_eq!(web.id(), "acme-web");
+    assert_eq!(web.name(), "acme-web");
+
+    // the index is fetched the first time it's needed
+    let (spec, _) = web.resolve_font(&FontInstallSpec::new_all_styles("acme-mono")).await.unwrap();
+    let paths = web.download_font(&spec, &host.cache_dir_for(web.id())).await.unwrap();
+    assert_file_contents(paths.values().next().unwrap(), FILES[3]);
+    assert_eq!(server.request_count("/registry/index.toml"), 1);
+    assert_eq!(server.request_count("/registry/acme-mono/AcmeMono[wght].ttf"), 1);
+
+    let (spec, _) = local.resolve_font(&FontInstallSpec::new_all_styles("acme-mono")).await.unwrap();
+    local.download_font(&spec, &host.cache_dir_for(local.id())).await.unwrap();
+    assert_eq!(server.request_count("/registry/acme-mono/AcmeMono[wght].ttf"), 1);
+    assert!(!installed_files(host.cache_dir_for("acme")).is_empty());
+    assert!(!installed_files(host.cache_dir_for("acme-web")).is_empty());
+}
+
+#[tokio::test]
+async fn registry_files_are_verified() {
+    let mut host = TempFpmHost::new();
+    let dir = host.path().join("acme");
+    write_registry(&dir);
+    fs::write(dir.join(FILES[0]), "<html>captive portal</html>").unwrap();
+    configure(&mut host, "acme", &format!("index = \"{}\"", dir.join("index.json").display()));
+
+    let mut source = RegistrySource::new("acme");
+    source.set_host(&host);
+    let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("acme-sans")).await.unwrap();
+    match source.download_font(&spec, &host.cache_dir_for(source.id())).await {
+        Err(Error::Integrity(message)) => assert!(message.contains(FILES[0])),
+        other => panic!("expected an integrity error, got {:?}", other)
+    }
+    assert!(!installed_files(host.cache_dir_for(source.id())).iter().any(|v| v.contains("400-regular")));
+}
+
+#[tokio::test]
+async fn invalid_registries() {
+    let mut host = TempFpmHost::new();
+    assert!(registry::create_source("acme", &host).unwrap().is_none());
+
+    host.set_config("acme", toml::from_str("type = \"no-such-type\"").unwrap());
+    assert!(registry::create_source("acme", &host).is_err());
+
+    configure(&mut host, "acme", "");
+    {
+        let source = registry::create_source("acme", &host).unwrap().unwrap();
+        assert!(source.refresh(false).await.err().unwrap().to_string().contains("index"));
+    }
+
+    let index = host.path().join("index.json");
+    fs::write(&index, r#"{"families": {"acme-sans": {"name": "ACME Sans", "version": "1", "variants": {"bold": {"path": "a.ttf"}}}}}"#).unwrap();
+    configure(&mut host, "acme", &format!("index = \"{}\"", index.display()));
+    let source = registry::create_source("acme", &host).unwrap().unwrap();
+    assert!(source.refresh(false).await.is_err());
+}
+
+#[tokio::test]
+async fn remote_registries_stay_remote() {
+    let server = FixtureServer::start();
+    let mut host = TempFpmHost::new();
+    let secret = host.path().join("secret.ttf");
+    fs::write(&secret, "secret").unwrap();
+    server.route("/registry/index.json", FixtureResponse::ok(format!(
+        r#"{{"families": {{"acme-sans": {{"name": "ACME Sans", "version": "1", "variants": {{"regular": {{"path": "file://{}"}}}}}}}}}}"#,
+        secret.display()
+    )));
+    server.route("/registry/escape.json", FixtureResponse::ok(
+        r#"{"families": {"acme-sans": {"name": "ACME Sans", "version": "../../../escaped", "variants": {"regular": {"path": "a.ttf"}}}}}"#
+    ));
+
+    configure(&mut host, "acme", &format!("index = \"{}\"", server.url_for("/registry/index.json")));
+    {
+        let source = registry::create_source("acme", &host).unwrap().unwrap();
+        let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("acme-sans")).await.unwrap();
+        let cache_dir = host.cache_dir_for(source.id());
+        assert!(source.download_font(&spec, &cache_dir).await.err().unwrap().to_string().contains("local file"));
+        assert!(installed_files(&cache_dir).iter().all(|v| !v.ends_with(".ttf")));
+    }
+
+    configure(&mut host, "acme", &format!("index = \"{}\"", server.url_for("/registry/escape.json")));
+    let source = registry::create_source("acme", &host).unwrap().unwrap();
+    assert!(source.refresh(false).await.is_err());
+    assert!(!host.path().join("escaped").exists());
+}
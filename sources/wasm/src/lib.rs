@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use reqwest::{Client, ClientBuilder, Url};
+use tokio::sync::Mutex;
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime::component::{Component as WasmComponent, HasSelf, Linker};
+use fontpm_api::{debug, error, info, trace, warning, Error, FpmHost, Source};
+use fontpm_api::async_trait::async_trait;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription, FontInstallSpec, FontStyle, FontVariantSpec, FontWeight};
+use fontpm_api::host::EmptyFpmHost;
+use fontpm_api::source::RefreshOutput;
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "source",
+        imports: { default: async },
+        exports: { default: async },
+    });
+}
+
+use bindings::Source as SourceWorld;
+use bindings::fontpm::source::host::LogLevel;
+use bindings::fontpm::source::types;
+
+/// Component files are named `fontpm-source-<id>.wasm`.
+pub const FILE_PREFIX: &str = "fontpm-source-";
+pub const FILE_EXTENSION: &str = "wasm";
+
+/// Finds the component for a specific source ID in `plugin_dir`.
+pub fn find(id: &str, plugin_dir: impl AsRef<Path>) -> Option<PathBuf> {
+    let path = plugin_dir.as_ref().join(format!("{}{}.{}", FILE_PREFIX, id, FILE_EXTENSION));
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// How much a component may use, so that a broken or malicious one can't hang or exhaust fontpm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The fuel (roughly, the number of WebAssembly instructions) each call to the component may use.
+    pub fuel: u64,
+    /// The most memory the component may use, in bytes.
+    pub memory: usize,
+    /// The largest response body `http-get` will read, in bytes.
+    pub response_size: usize,
+    /// How long a single `http-get` request may take, including reading the response.
+    pub request_timeout: Duration
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            fuel: 10_000_000_000,
+            memory: 256 * 1024 * 1024,
+            response_size: 64 * 1024 * 1024,
+            request_timeout: Duration::from_secs(60)
+        }
+    }
+}
+
+/// Resolves a path given by a component against `root`,
+/// refusing anything that could point outside of it.
+fn sandboxed_path(root: &Path, path: &str) -> Result<PathBuf, Error> {
+    let relative = Path::new(path);
+    let escapes = relative.components()
+        .any(|v| !matches!(v, Component::Normal(_) | Component::CurDir));
+    if path.is_empty() || escapes {
+        return Err(Error::Generic(format!("path {} is outside of the cache directory", path)))
+    }
+    Ok(root.join(relative))
+}
+
+/// Parses a URL given by a component, refusing anything but HTTP and HTTPS.
+fn web_url(url: &str) -> Result<Url, Error> {
+    let parsed = Url::parse(url).map_err(|v| Error::Generic(format!("invalid URL {}: {}", url, v)))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(Error::Generic(format!("URL {} has the scheme {}, but only http and https are allowed", url, scheme)))
+    }
+}
+
+/// Data available to the host functions of a single component instance.
+struct State {
+    id: String,
+    cache_dir: PathBuf,
+    config: Option<String>,
+    client: Client,
+    limits: Limits,
+    store_limits: StoreLimits
+}
+
+impl types::Host for State {}
+
+impl bindings::fontpm::source::host::Host for State {
+    async fn log(&mut self, level: LogLevel, message: String) {
+        match level {
+            LogLevel::Error => { error!("[{}] {}", self.id, message); }
+            LogLevel::Warning => { warning!("[{}] {}", self.id, message); }
+            LogLevel::Info => { info!("[{}] {}", self.id, message); }
+            LogLevel::Debug => { debug!("[{}] {}", self.id, message); }
+            LogLevel::Trace => { trace!("[{}] {}", self.id, message); }
+        }
+    }
+
+    async fn config(&mut self) -> Option<String> {
+        self.config.clone()
+    }
+
+    async fn read_cache_file(&mut self, path: String) -> Result<Vec<u8>, types::Error> {
+        let path = sandboxed_path(&self.cache_dir, &path)?;
+        Ok(tokio::fs::read(path).await.map_err(Error::from)?)
+    }
+
+    async fn write_cache_file(&mut self, path: String, contents: Vec<u8>) -> Result<(), types::Error> {
+        let path = sandboxed_path(&self.cache_dir, &path)?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).map_err(Error::from)?;
+        }
+        Ok(tokio::fs::write(path, contents).await.map_err(Error::from)?)
+    }
+
+    async fn cache_file_exists(&mut self, path: String) -> bool {
+        sandboxed_path(&self.cache_dir, &path).is_ok_and(|v| v.exists())
+    }
+
+    async fn http_get(&mut self, url: String) -> Result<Vec<u8>, types::Error> {
+        trace!("[{}] GET {}", self.id, url);
+        let mut response = self.client.get(web_url(&url)?).send().await
+            .and_then(|v| v.error_for_status())
+            .map_err(Error::from)?;
+        let max = self.limits.response_size;
+        let too_large = || types::Error::Generic(format!("the response from {} is larger than {} bytes", url, max));
+        if response.content_length().is_some_and(|v| v > max as u64) {
+            return Err(too_large())
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(Error::from)? {
+            if body.len() + chunk.len() > max {
+                return Err(too_large())
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+impl From<Error> for types::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::ConnectionError(v) => types::Error::Connection(v),
+            Error::IO(v) => types::Error::Io(v.to_string()),
+            Error::Deserialisation(v) => types::Error::Deserialisation(v),
+            Error::NoSuchFamily(v) => types::Error::NoSuchFamily(v),
+            other => types::Error::Generic(other.to_string())
+        }
+    }
+}
+impl From<types::Error> for Error {
+    fn from(value: types::Error) -> Self {
+        match value {
+            types::Error::Generic(v) => Error::Generic(v),
+            types::Error::Connection(v) => Error::ConnectionError(v),
+            types::Error::Io(v) => Error::IO(std::io::Error::other(v)),
+            types::Error::Deserialisation(v) => Error::Deserialisation(v),
+            types::Error::NoSuchFamily(v) => Error::NoSuchFamily(v)
+        }
+    }
+}
+
+impl From<&FontVariantSpec> for types::VariantSpec {
+    fn from(value: &FontVariantSpec) -> Self {
+        types::VariantSpec {
+            weight: match value.weight {
+                FontWeight::Defined(DefinedFontWeight::Fixed(weight)) => types::Weight::Fixed(weight),
+                FontWeight::Defined(DefinedFontWeight::Variable) => types::Weight::Variable,
+                FontWeight::AllFixed => types::Weight::AllFixed,
+                FontWeight::All => types::Weight::All
+            },
+            style: match value.style {
+                FontStyle::Defined(DefinedFontStyle::Regular) => types::Style::Regular,
+                FontStyle::Defined(DefinedFontStyle::Italic) => types::Style::Italic,
+                FontStyle::All => types::Style::All
+            }
+        }
+    }
+}
+impl From<&DefinedFontVariantSpec> for types::VariantSpec {
+    fn from(value: &DefinedFontVariantSpec) -> Self {
+        types::VariantSpec::from(&FontVariantSpec {
+            weight: FontWeight::Defined(value.weight),
+            style: FontStyle::Defined(value.style)
+        })
+    }
+}
+impl TryFrom<types::VariantSpec> for DefinedFontVariantSpec {
+    type Error = Error;
+
+    fn try_from(value: types::VariantSpec) -> Result<Self, Self::Error> {
+        let weight = match value.weight {
+            types::Weight::Fixed(weight) => DefinedFontWeight::Fixed(weight),
+            types::Weight::Variable => DefinedFontWeight::Variable,
+            _ => return Err(Error::Deserialisation("expected a defined font weight".into()))
+        };
+        let style = match value.style {
+            types::Style::Regular => DefinedFontStyle::Regular,
+            types::Style::Italic => DefinedFontStyle::Italic,
+            types::Style::All => return Err(Error::Deserialisation("expected a defined font style".into()))
+        };
+        Ok(DefinedFontVariantSpec { weight, style })
+    }
+}
+impl TryFrom<types::InstallSpec> for DefinedFontInstallSpec {
+    type Error = Error;
+
+    fn try_from(value: types::InstallSpec) -> Result<Self, Self::Error> {
+        let styles: Result<Vec<DefinedFontVariantSpec>, Error> = value.styles.into_iter()
+            .map(TryInto::try_into)
+            .collect();
+        Ok(DefinedFontInstallSpec::new(value.id, styles?))
+    }
+}
+impl From<types::FontDescription> for FontDescription {
+    fn from(value: types::FontDescription) -> Self {
+        FontDescription::new(value.name, value.id, value.version)
+    }
+}
+
+fn trap(id: &str, error: wasmtime::Error) -> Error {
+    Error::Generic(format!("component {} trapped: {:#}", id, error))
+}
+
+struct Instance {
+    store: Store<State>,
+    bindings: SourceWorld
+}
+
+/// A source implemented by a WebAssembly component (see `wit/source.wit`).
+///
+/// Components can only reach the outside world through the functions in the `host` interface:
+/// they can read and write files in their own cache directory, send GET requests through fontpm's HTTP client, and log.
+/// The component is compiled when the source is loaded, and instantiated on the first request.
+/// Each call to it is bounded by [`Limits`].
+pub struct WasmSource<'host> {
+    id: String,
+    host: &'host dyn FpmHost,
+    limits: Limits,
+    engine: Engine,
+    component: WasmComponent,
+    name: OnceLock<String>,
+    instance: Mutex<Option<Instance>>
+}
+
+impl<'host> WasmSource<'host> {
+    pub fn load(id: impl ToString, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let id = id.to_string();
+        let mut config = Config::new();
+        config.async_support(true);
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)
+            .map_err(|v| Error::Generic(format!("could not create WebAssembly engine: {}", v)))?;
+        let component = WasmComponent::from_file(&engine, path.as_ref())
+            .map_err(|v| Error::Generic(format!("could not load component {}: {}", path.as_ref().display(), v)))?;
+        Ok(WasmSource {
+            id,
+            host: &EmptyFpmHost::EMPTY_HOST,
+            limits: Limits::default(),
+            engine,
+            component,
+            name: OnceLock::new(),
+            instance: Mutex::new(None)
+        })
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    async fn instantiate(&self) -> Result<Instance, Error> {
+        let config = match self.host.config(self.id.clone()) {
+            Some(config) => Some(serde_json::to_string(config).map_err(|v| Error::Serialisation(v.to_string()))?),
+            None => None
+        };
+        let client = ClientBuilder::new()
+            .user_agent(self.host.user_agent())
+            .timeout(self.limits.request_timeout)
+            .build()?;
+        let mut store = Store::new(&self.engine, State {
+            id: self.id.clone(),
+            cache_dir: self.host.cache_dir_for(&self.id),
+            config,
+            client,
+            limits: self.limits,
+            store_limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.memory)
+                .trap_on_grow_failure(true)
+                .build()
+        });
+        store.limiter(|v| &mut v.store_limits);
+        store.set_fuel(self.limits.fuel).map_err(|v| trap(&self.id, v))?;
+
+        let mut linker = Linker::new(&self.engine);
+        SourceWorld::add_to_linker::<State, HasSelf<State>>(&mut linker, |v| v)
+            .map_err(|v| trap(&self.id, v))?;
+        let bindings = SourceWorld::instantiate_async(&mut store, &self.component, &linker).await
+            .map_err(|v| Error::Generic(format!("could not instantiate component {}: {}", self.id, v)))?;
+
+        let name = bindings.call_name(&mut store).await.map_err(|v| trap(&self.id, v))?;
+        let _ = self.name.set(name);
+        Ok(Instance { store, bindings })
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.host.cache_dir_for(&self.id)
+    }
+}
+
+/// Runs `$call` on the (possibly newly created) instance, converting traps and guest errors.
+macro_rules! call {
+    ($self:ident, |$bindings:ident, $store:ident| $call:expr) => {{
+        let mut instance = $self.instance.lock().await;
+        if instance.is_none() {
+            *instance = Some($self.instantiate().await?);
+        }
+        let Instance { store: $store, bindings: $bindings } = instance.as_mut().unwrap();
+        // every call gets the same amount of fuel, however much the previous ones used
+        $store.set_fuel($self.limits.fuel).map_err(|e| trap(&$self.id, e))?;
+        match $call.await {
+            Ok(result) => result.map_err(Error::from),
+            Err(e) => {
+                // a trapped instance can't be used any more
+                *instance = None;
+                Err(trap(&$self.id, e))
+            }
+        }
+    }};
+}
+
+#[async_trait]
+impl<'host> Source<'host> for WasmSource<'host> {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    /// The name reported by the component, or the ID if it hasn't been instantiated yet.
+    fn name(&self) -> &str {
+        self.name.get().unwrap_or(&self.id)
+    }
+
+    fn set_host(&mut self, host: &'host dyn FpmHost) {
+        self.host = host;
+    }
+
+    async fn refresh(&self, force: bool) -> Result<RefreshOutput, Error> {
+        let output = call!(self, |bindings, store| bindings.call_refresh(store, force))?;
+        Ok(match output {
+            types::RefreshOutput::AlreadyUpToDate => RefreshOutput::AlreadyUpToDate,
+            types::RefreshOutput::Downloaded => RefreshOutput::Downloaded
+        })
+    }
+
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+        let spec = types::InstallSpec {
+            id: spec.id.clone(),
+            styles: spec.styles.iter().map(Into::into).collect()
+        };
+        let (spec, description) = call!(self, |bindings, store| bindings.call_resolve_font(store, &spec))?;
+        Ok((spec.try_into()?, description.into()))
+    }
+
+    /// Components always download into their cache directory, so `_dir` is ignored.
+    async fn download_font(&self, spec: &DefinedFontInstallSpec, _dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let spec = types::InstallSpec {
+            id: spec.id.clone(),
+            styles: spec.styles.iter().map(Into::into).collect()
+        };
+        let files = call!(self, |bindings, store| bindings.call_download_font(store, &spec))?;
+
+        let cache_dir = self.cache_dir();
+        let mut paths = HashMap::new();
+        for file in files {
+            paths.insert(file.spec.try_into()?, sandboxed_path(&cache_dir, &file.path)?);
+        }
+        Ok(paths)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let fonts = call!(self, |bindings, store| bindings.call_search(store, query))?;
+        Ok(fonts.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_stay_in_sandbox() {
+        let root = Path::new("/cache/source");
+        assert_eq!(sandboxed_path(root, "index.json").unwrap(), root.join("index.json"));
+        assert_eq!(sandboxed_path(root, "./fonts/a.ttf").unwrap(), root.join("./fonts/a.ttf"));
+        assert!(sandboxed_path(root, "").is_err());
+        assert!(sandboxed_path(root, "../other/index.json").is_err());
+        assert!(sandboxed_path(root, "fonts/../../other").is_err());
+        assert!(sandboxed_path(root, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn only_web_urls() {
+        assert!(web_url("https://fonts.example.com/index.json").is_ok());
+        assert!(web_url("http://localhost:8080/index.json").is_ok());
+        assert!(web_url("file:///etc/passwd").is_err());
+        assert!(web_url("ftp://fonts.example.com/index.json").is_err());
+        assert!(web_url("fonts.example.com/index.json").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+;; A source that misbehaves on purpose: `refresh` never returns, and `resolve-font` uses as much memory as it can.
+;; `search` always succeeds with no results.
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    ;; the name, and the (pointer, length) pair `name` returns
+    (data (i32.const 16) "Fixture")
+    (data (i32.const 32) "\10\00\00\00\07\00\00\00")
+    ;; 64..76 is left as zeros, which is `ok` with an empty list
+
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.sub (i32.const 0) (local.get 2))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    (func (export "name") (result i32)
+      (i32.const 32))
+    (func (export "refresh") (param i32) (result i32)
+      (loop $forever (br $forever))
+      (unreachable))
+    (func (export "resolve-font") (param i32 i32 i32 i32) (result i32)
+      (loop $grow
+        (br_if $grow (i32.ne (memory.grow (i32.const 256)) (i32.const -1))))
+      (unreachable))
+    (func (export "download-font") (param i32 i32 i32 i32) (result i32)
+      (unreachable))
+    (func (export "search") (param i32 i32) (result i32)
+      (i32.const 64))
+  )
+  (core instance $i (instantiate $m))
+
+  (type $weight' (variant (case "fixed" u32) (case "variable") (case "all-fixed") (case "all")))
+  (export $weight "weight" (type $weight'))
+  (type $style' (enum "regular" "italic" "all"))
+  (export $style "style" (type $style'))
+  (type $variant-spec' (record (field "weight" $weight) (field "style" $style)))
+  (export $variant-spec "variant-spec" (type $variant-spec'))
+  (type $install-spec' (record (field "id" string) (field "styles" (list $variant-spec))))
+  (export $install-spec "install-spec" (type $install-spec'))
+  (type $font-description' (record (field "name" string) (field "id" string) (field "version" string)))
+  (export $font-description "font-description" (type $font-description'))
+  (type $downloaded-file' (record (field "spec" $variant-spec) (field "path" string)))
+  (export $downloaded-file "downloaded-file" (type $downloaded-file'))
+  (type $refresh-output' (enum "already-up-to-date" "downloaded"))
+  (export $refresh-output "refresh-output" (type $refresh-output'))
+  (type $error' (variant
+    (case "generic" string)
+    (case "connection" string)
+    (case "io" string)
+    (case "deserialisation" string)
+    (case "no-such-family" string)))
+  (export $error "error" (type $error'))
+
+  (func (export "name") (result string)
+    (canon lift (core func $i "name") (memory $i "memory")))
+  (func (export "refresh") (param "force" bool) (result (result $refresh-output (error $error)))
+    (canon lift (core func $i "refresh") (memory $i "memory")))
+  (func (export "resolve-font") (param "spec" $install-spec) (result (result (tuple $install-spec $font-description) (error $error)))
+    (canon lift (core func $i "resolve-font") (memory $i "memory") (realloc (func $i "realloc"))))
+  (func (export "download-font") (param "spec" $install-spec) (result (result (list $downloaded-file) (error $error)))
+    (canon lift (core func $i "download-font") (memory $i "memory") (realloc (func $i "realloc"))))
+  (func (export "search") (param "query" string) (result (result (list $font-description) (error $error)))
+    (canon lift (core func $i "search") (memory $i "memory") (realloc (func $i "realloc"))))
+)
@@ -0,0 +1,40 @@
+use fontpm_api::Source;
+use fontpm_api::font::FontInstallSpec;
+use fontpm_source_wasm::{Limits, WasmSource};
+use fontpm_testing::TempFpmHost;
+
+fn load(host: &TempFpmHost) -> WasmSource<'_> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/limits.wat");
+    let mut source = WasmSource::load("limits", path).unwrap()
+        .with_limits(Limits { fuel: 10_000_000, memory: 4 * 1024 * 1024, ..Limits::default() });
+    source.set_host(host);
+    source
+}
+
+#[tokio::test]
+async fn components_are_called() {
+    let host = TempFpmHost::new();
+    let source = load(&host);
+    assert_eq!(source.name(), "limits");
+    assert!(source.search("sans").await.unwrap().is_empty());
+    assert_eq!(source.name(), "Fixture");
+}
+
+#[tokio::test]
+async fn components_run_out_of_fuel() {
+    let host = TempFpmHost::new();
+    let source = load(&host);
+    let error = source.refresh(false).await.err().unwrap().to_string();
+    assert!(error.contains("all fuel consumed"), "{}", error);
+    // the trapped instance is replaced
+    assert!(source.search("sans").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn components_run_out_of_memory() {
+    let host = TempFpmHost::new();
+    let source = load(&host);
+    let error = source.resolve_font(&FontInstallSpec::new_all_styles("acme-sans")).await.err().unwrap().to_string();
+    assert!(error.contains("growing memory"), "{}", error);
+    assert!(source.search("sans").await.unwrap().is_empty());
+}
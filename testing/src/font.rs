@@ -0,0 +1,175 @@
+//! Minimal font files for sources that read fonts instead of downloading them.
+
+/// A TrueType font without any glyphs, which only has the tables needed to be parsed
+/// and the names and style that describe it.
+#[derive(Clone, Debug)]
+pub struct FixtureFont {
+    pub family: String,
+    pub weight: u16,
+    pub italic: bool,
+    /// Whether the font has a weight axis.
+    pub variable: bool,
+    /// The version name (e.g. `Version 1.000`).
+    pub version: String
+}
+
+impl FixtureFont {
+    pub fn new(family: impl ToString) -> Self {
+        FixtureFont {
+            family: family.to_string(),
+            weight: 400,
+            italic: false,
+            variable: false,
+            version: "Version 1.000".into()
+        }
+    }
+    pub fn with_weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+    pub fn variable(mut self) -> Self {
+        self.variable = true;
+        self
+    }
+    pub fn with_version(mut self, version: impl ToString) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    fn subfamily(&self) -> &'static str {
+        match (self.weight >= 600, self.italic) {
+            (false, false) => "Regular",
+            (false, true) => "Italic",
+            (true, false) => "Bold",
+            (true, true) => "Bold Italic"
+        }
+    }
+
+    /// The font file.
+    pub fn build(&self) -> Vec<u8> {
+        let mut tables = vec![
+            (*b"OS/2", self.os2()),
+            (*b"head", head()),
+            (*b"hhea", hhea()),
+            (*b"maxp", maxp()),
+            (*b"name", self.name())
+        ];
+        if self.variable {
+            tables.push((*b"fvar", fvar()));
+        }
+        sfnt(tables)
+    }
+
+    fn os2(&self) -> Vec<u8> {
+        let mut table = vec![0u8; 78];
+        table[4..6].copy_from_slice(&self.weight.to_be_bytes());
+        // usWidthClass: medium
+        table[6..8].copy_from_slice(&5u16.to_be_bytes());
+        // fsSelection: italic or regular
+        let selection: u16 = if self.italic { 1 } else { 1 << 6 };
+        table[62..64].copy_from_slice(&selection.to_be_bytes());
+        table
+    }
+
+    fn name(&self) -> Vec<u8> {
+        let full_name = format!("{} {}", self.family, self.subfamily());
+        let names = [
+            (1, self.family.as_str()),
+            (2, self.subfamily()),
+            (4, full_name.as_str()),
+            (5, self.version.as_str())
+        ];
+
+        let mut records = Vec::new();
+        let mut strings = Vec::new();
+        for (id, value) in names {
+            let value: Vec<u8> = value.encode_utf16().flat_map(u16::to_be_bytes).collect();
+            // Windows, Unicode BMP, English (United States)
+            for v in [3u16, 1, 0x409, id, value.len() as u16, strings.len() as u16] {
+                records.extend_from_slice(&v.to_be_bytes());
+            }
+            strings.extend(value);
+        }
+
+        let mut table = Vec::new();
+        for v in [0u16, names.len() as u16, 6 + records.len() as u16] {
+            table.extend_from_slice(&v.to_be_bytes());
+        }
+        table.extend(records);
+        table.extend(strings);
+        table
+    }
+}
+
+fn head() -> Vec<u8> {
+    let mut table = vec![0u8; 54];
+    // version 1.0
+    table[0..2].copy_from_slice(&1u16.to_be_bytes());
+    table[12..16].copy_from_slice(&0x5F0F3CF5u32.to_be_bytes());
+    // unitsPerEm
+    table[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    table
+}
+
+fn hhea() -> Vec<u8> {
+    let mut table = vec![0u8; 36];
+    table[0..2].copy_from_slice(&1u16.to_be_bytes());
+    table
+}
+
+fn maxp() -> Vec<u8> {
+    let mut table = 0x00005000u32.to_be_bytes().to_vec();
+    // numGlyphs, which has to be at least 1
+    table.extend_from_slice(&1u16.to_be_bytes());
+    table
+}
+
+/// A `wght` axis from 100 to 900.
+fn fvar() -> Vec<u8> {
+    let mut table = Vec::new();
+    table.extend_from_slice(&0x00010000u32.to_be_bytes());
+    // axesArrayOffset, reserved, axisCount, axisSize, instanceCount, instanceSize
+    for v in [16u16, 2, 1, 20, 0, 8] {
+        table.extend_from_slice(&v.to_be_bytes());
+    }
+    table.extend_from_slice(b"wght");
+    for v in [100u32, 400, 900] {
+        table.extend_from_slice(&(v << 16).to_be_bytes());
+    }
+    // flags, axisNameID
+    table.extend_from_slice(&[0, 0, 1, 0]);
+    table
+}
+
+/// Puts `tables` together into a font file.
+fn sfnt(mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = (1 << entry_selector) * 16;
+
+    let mut font = 0x00010000u32.to_be_bytes().to_vec();
+    for v in [count, search_range, entry_selector, count * 16 - search_range] {
+        font.extend_from_slice(&v.to_be_bytes());
+    }
+    let mut offset = 12 + 16 * tables.len();
+    let mut data = Vec::new();
+    for (tag, table) in &tables {
+        font.extend_from_slice(tag);
+        // checksums aren't checked by anything that reads these fonts
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        data.extend_from_slice(table);
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        offset = 12 + 16 * tables.len() + data.len();
+    }
+    font.extend(data);
+    font
+}
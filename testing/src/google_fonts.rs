@@ -0,0 +1,32 @@
+//! A stand-in for the Google Fonts index and the hosts its files are on, for testing the google-fonts source and what's built on it.
+
+use crate::server::{FixtureResponse, FixtureServer};
+
+/// The fixture index, with the families Test Sans and Test Mono.
+pub const INDEX: &str = include_str!("../fixtures/google-fonts.json");
+/// Every file in [`INDEX`], without the scheme.
+pub const FILES: &[&str] = &[
+    "fonts.example.com/s/testsans/v3/TestSans-Regular.ttf",
+    "fonts.example.com/s/testsans/v3/TestSans-Italic.ttf",
+    "fonts.example.com/s/testsans/v3/TestSans-Bold.ttf",
+    "fonts.example.com/s/testmono/v1/TestMono-Regular.ttf",
+    "fonts.example.com/s/testsans/v3/TestSans-Variable.ttf",
+    "fonts.example.com/s/testsans/v3/TestSans-VariableItalic.ttf",
+    "fonts.example.com/s/testsans/v3/TestSans-Regular-latin.woff2",
+    "fonts.example.com/s/testsans/v3/TestSans-Bold-latin.woff2",
+    "fonts.example.com/s/testsans/v3/TestSans-Regular-cyrillic.woff2",
+    "fonts.example.com/s/testsans/v3/TestSans-Bold-cyrillic.woff2",
+    "raw.example.com/google/fonts/main/ofl/testsans/OFL.txt"
+];
+
+/// Serves [`INDEX`] at `/data/google-fonts.json`, the latest commit of the index at `/branches/data`,
+/// and every file in [`FILES`] at `/<file>` (each containing its own path).
+pub fn google_fonts_fixture_server() -> FixtureServer {
+    let server = FixtureServer::start();
+    server.route("/data/google-fonts.json", FixtureResponse::ok(INDEX));
+    server.route("/branches/data", FixtureResponse::ok(r#"{"commit": {"sha": "0123abc"}}"#));
+    for file in FILES {
+        server.route(format!("/{}", file), FixtureResponse::ok(*file));
+    }
+    server
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use semver::Version;
+use tempfile::TempDir;
+use fontpm_api::FpmHost;
+use fontpm_api::host::{toml, DEFAULT_MAX_CONCURRENT_DOWNLOADS};
+
+/// A host whose cache and install directories are inside a temporary directory,
+/// which is deleted when the host is dropped.
+///
+/// Creating a host also sets up test output (see [`crate::output::init`]).
+pub struct TempFpmHost {
+    dir: TempDir,
+    config: HashMap<String, toml::Value>,
+    version: Version,
+    max_concurrent_downloads: usize
+}
+
+impl TempFpmHost {
+    pub fn new() -> Self {
+        crate::output::init();
+        TempFpmHost {
+            dir: tempfile::Builder::new().prefix("fontpm-test").tempdir().expect("could not create temporary directory"),
+            config: HashMap::new(),
+            version: Version::new(0, 0, 0),
+            max_concurrent_downloads: DEFAULT_MAX_CONCURRENT_DOWNLOADS
+        }
+    }
+
+    /// Sets the configuration for a source, as if it was in `[sources.<id>]`.
+    pub fn with_config(mut self, id: impl ToString, config: toml::Value) -> Self {
+        self.set_config(id, config);
+        self
+    }
+    pub fn set_config(&mut self, id: impl ToString, config: toml::Value) {
+        self.config.insert(id.to_string(), config);
+    }
+
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_max_concurrent_downloads(mut self, max: usize) -> Self {
+        self.max_concurrent_downloads = max;
+        self
+    }
+
+    /// The temporary directory everything is stored in.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl Default for TempFpmHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FpmHost for TempFpmHost {
+    fn global_cache_dir(&self) -> PathBuf {
+        self.dir.path().join("cache")
+    }
+
+    fn cache_dir_for(&self, id: &str) -> PathBuf {
+        self.global_cache_dir().join(id)
+    }
+
+    fn font_install_dir(&self) -> PathBuf {
+        self.dir.path().join("fonts")
+    }
+
+    fn config(&self, id: String) -> Option<&toml::Value> {
+        self.config.get(&id)
+    }
+
+    fn version(&self) -> Version {
+        self.version.clone()
+    }
+
+    fn user_agent(&self) -> String {
+        format!("FontPM-Testing/{}", self.version())
+    }
+
+    fn max_concurrent_downloads(&self) -> usize {
+        self.max_concurrent_downloads
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::Path;
+
+/// Lists every file below `dir`, as sorted `/`-separated paths relative to `dir`.
+/// Returns an empty list if `dir` doesn't exist.
+pub fn installed_files(dir: impl AsRef<Path>) -> Vec<String> {
+    fn visit(root: &Path, dir: &Path, files: &mut Vec<String>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(v) => v,
+            Err(_) => return
+        };
+        for entry in entries {
+            let path = entry.expect("could not read directory entry").path();
+            if path.is_dir() {
+                visit(root, &path, files);
+            } else {
+                let relative = path.strip_prefix(root).unwrap();
+                let components: Vec<String> = relative.components()
+                    .map(|v| v.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                files.push(components.join("/"));
+            }
+        }
+    }
+
+    let dir = dir.as_ref();
+    let mut files = Vec::new();
+    visit(dir, dir, &mut files);
+    files.sort();
+    files
+}
+
+/// Asserts that the files below `dir` are exactly `expected` (in any order).
+#[track_caller]
+pub fn assert_installed<S: AsRef<str>>(dir: impl AsRef<Path>, expected: &[S]) {
+    let mut expected: Vec<&str> = expected.iter().map(AsRef::as_ref).collect();
+    expected.sort();
+    assert_eq!(installed_files(dir.as_ref()), expected, "unexpected files in {}", dir.as_ref().display());
+}
+
+/// Asserts that the file at `path` exists and contains exactly `contents`.
+#[track_caller]
+pub fn assert_file_contents(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+    let path = path.as_ref();
+    let actual = fs::read(path).unwrap_or_else(|e| panic!("could not read {}: {}", path.display(), e));
+    assert!(actual == contents.as_ref(), "unexpected contents in {}", path.display());
+}
@@ -0,0 +1,22 @@
+//! Utilities for testing code that uses fontpm's API without touching the network or the user's files.
+//!
+//! - [`TempFpmHost`] is an [`FpmHost`](fontpm_api::FpmHost) whose directories live in a temporary directory.
+//! - [`InMemorySource`] is a [`Source`](fontpm_api::Source) serving fixture families from memory.
+//! - [`layout`] has helpers for checking which files ended up where.
+//! - [`FixtureServer`] is a local HTTP server for sources that download things,
+//!   and [`google_fonts_fixture_server`] serves a fixture Google Fonts index on one.
+//! - [`FixtureFont`] builds minimal font files for sources that read fonts.
+
+pub mod host;
+pub mod output;
+pub mod source;
+pub mod layout;
+pub mod server;
+pub mod font;
+pub mod google_fonts;
+
+pub use host::TempFpmHost;
+pub use source::{FixtureFamily, InMemorySource};
+pub use server::{FixtureResponse, FixtureServer};
+pub use font::FixtureFont;
+pub use google_fonts::google_fonts_fixture_server;
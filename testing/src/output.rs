@@ -0,0 +1,34 @@
+use std::sync::Once;
+use fontpm_api::Error;
+use fontpm_api::output::{CliOutput, OutputKind, OutputRecord, set_impl};
+
+/// Prints every message to stderr, so that it is captured by the test harness.
+struct TestOutput;
+
+impl CliOutput for TestOutput {
+    fn is_enabled(&self, _: OutputKind) -> bool {
+        true
+    }
+
+    fn log(&self, record: OutputRecord) -> Result<(), Error> {
+        let prefix = match record.kind {
+            OutputKind::Ok => "ok!",
+            OutputKind::Error => "error!",
+            OutputKind::Warning => "warning!",
+            OutputKind::Info => "info",
+            OutputKind::Debug => "debug",
+            OutputKind::Trace => "trace"
+        };
+        eprintln!("{} {}", prefix, record.message.trim_end());
+        Ok(())
+    }
+}
+
+static OUTPUT: TestOutput = TestOutput;
+static INIT: Once = Once::new();
+
+/// Sets up an output implementation for the output macros (`info!`, `trace!`, etc.), so that what they log shows up in the test output instead of being discarded.
+/// Can safely be called any number of times, but must not be combined with other calls to [`set_impl`].
+pub fn init() {
+    INIT.call_once(|| set_impl(&OUTPUT));
+}
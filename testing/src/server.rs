@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A response served by a [`FixtureServer`].
+#[derive(Clone, Debug)]
+pub struct FixtureResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// How long to wait before responding.
+    pub delay: Option<Duration>,
+    /// If set, the connection is closed after this many bytes of the body, as if it broke.
+    pub interrupt_after: Option<usize>,
+    /// Whether to serve `Range: bytes=N-` requests with a `206 Partial Content` response.
+    pub ranges: bool
+}
+
+impl FixtureResponse {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::status(200, body)
+    }
+    pub fn status(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        FixtureResponse {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+            delay: None,
+            interrupt_after: None,
+            ranges: false
+        }
+    }
+    pub fn with_header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+    /// Announces the whole body, but only sends the first `bytes` of it.
+    pub fn interrupted_after(mut self, bytes: usize) -> Self {
+        self.interrupt_after = Some(bytes);
+        self
+    }
+    pub fn with_range_support(mut self) -> Self {
+        self.ranges = true;
+        self.headers.push(("Accept-Ranges".into(), "bytes".into()));
+        self
+    }
+
+    /// The part of this response that `range` (a `Range` header) asks for, if this response supports ranges.
+    fn range(mut self, range: Option<&str>) -> Self {
+        let start = range
+            .filter(|_| self.ranges && self.status == 200)
+            .and_then(|v| v.strip_prefix("bytes="))
+            .and_then(|v| v.strip_suffix('-'))
+            .and_then(|v| v.parse::<usize>().ok());
+        match start {
+            Some(start) if start < self.body.len() => {
+                self.headers.push(("Content-Range".into(), format!("bytes {}-{}/{}", start, self.body.len() - 1, self.body.len())));
+                self.status = 206;
+                self.body.drain(..start);
+                self.interrupt_after = self.interrupt_after.map(|v| v.saturating_sub(start));
+            },
+            Some(_) => {
+                self.headers.push(("Content-Range".into(), format!("bytes */{}", self.body.len())));
+                self.status = 416;
+                self.body.clear();
+                self.interrupt_after = None;
+            },
+            None => {}
+        }
+        self
+    }
+}
+
+/// A request received by a [`FixtureServer`].
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercase.
+    pub headers: HashMap<String, String>
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The responses for each path, of which the first is served and removed until only one is left.
+    routes: HashMap<String, Vec<FixtureResponse>>,
+    requests: Vec<RecordedRequest>,
+    in_flight: usize,
+    max_in_flight: usize
+}
+
+/// A minimal HTTP/1.1 server on localhost, serving fixed responses by path.
+/// Unknown paths get a 404. The server stops when it is dropped.
+///
+/// It runs on its own threads (one per connection), so it works both inside and outside of an async runtime.
+pub struct FixtureServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl FixtureServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("could not bind fixture server");
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break
+                    }
+                    if let Ok(stream) = stream {
+                        let state = state.clone();
+                        thread::spawn(move || {
+                            // errors just mean the client went away
+                            let _ = handle(stream, &state);
+                        });
+                    }
+                }
+            })
+        };
+
+        FixtureServer {
+            address,
+            state,
+            stopped,
+            thread: Some(thread)
+        }
+    }
+
+    /// Serves `response` for requests to `path` (which should start with `/`).
+    pub fn route(&self, path: impl ToString, response: FixtureResponse) -> &Self {
+        self.route_sequence(path, vec![response])
+    }
+    /// Serves each of `responses` in turn for requests to `path`, and then keeps serving the last one
+    /// (e.g. to fail a few times before succeeding).
+    pub fn route_sequence(&self, path: impl ToString, responses: Vec<FixtureResponse>) -> &Self {
+        assert!(!responses.is_empty(), "a route needs at least one response");
+        self.state.lock().unwrap().routes.insert(path.to_string(), responses);
+        self
+    }
+
+    /// The URL of this server, without a trailing slash (e.g. `http://127.0.0.1:1234`).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+    /// The URL of `path` on this server.
+    pub fn url_for(&self, path: impl AsRef<str>) -> String {
+        format!("{}{}", self.url(), path.as_ref())
+    }
+
+    /// Every request received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+    /// How many requests have been made to `path`.
+    pub fn request_count(&self, path: &str) -> usize {
+        self.state.lock().unwrap().requests.iter().filter(|v| v.path == path).count()
+    }
+    /// The most requests that have been responded to at the same time so far.
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.state.lock().unwrap().max_in_flight
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake up the listener so that it notices
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(stream: TcpStream, state: &Mutex<State>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        let range = headers.get("range").cloned();
+        state.requests.push(RecordedRequest { method: method.clone(), path: path.clone(), headers });
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+        let response = match state.routes.get_mut(&path) {
+            Some(responses) if responses.len() > 1 => responses.remove(0),
+            Some(responses) => responses[0].clone(),
+            None => FixtureResponse::status(404, "not found")
+        };
+        response.range(range.as_deref())
+    };
+    if let Some(delay) = response.delay {
+        thread::sleep(delay);
+    }
+    let result = write_response(stream, &method, response);
+    state.lock().unwrap().in_flight -= 1;
+    result
+}
+
+fn write_response(mut stream: TcpStream, method: &str, response: FixtureResponse) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} Fixture\r\nContent-Length: {}\r\nConnection: close\r\n", response.status, response.body.len());
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    if method != "HEAD" {
+        let len = response.interrupt_after.unwrap_or(usize::MAX).min(response.body.len());
+        stream.write_all(&response.body[..len])?;
+    }
+    stream.flush()
+}
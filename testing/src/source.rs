@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use fontpm_api::{Error, FpmHost, Source};
+use fontpm_api::async_trait::async_trait;
+use fontpm_api::font::{DefinedFontInstallSpec, DefinedFontStyle, DefinedFontVariantSpec, DefinedFontWeight, FontDescription, FontInstallSpec, FontStyle, FontVariantSpec, FontWeight};
+use fontpm_api::host::EmptyFpmHost;
+use fontpm_api::source::{RefreshOutput, TaggedFont};
+
+/// A font family served by an [`InMemorySource`].
+#[derive(Clone, Debug)]
+pub struct FixtureFamily {
+    pub description: FontDescription,
+    /// The extension and contents of the file for each variant.
+    pub files: BTreeMap<DefinedFontVariantSpec, (String, Vec<u8>)>,
+    /// The contents of each license document, by file name.
+    pub licenses: BTreeMap<String, Vec<u8>>,
+    /// Variants that are resolved, but fail to download.
+    pub unavailable: BTreeSet<DefinedFontVariantSpec>,
+    pub tags: Vec<String>
+}
+
+impl FixtureFamily {
+    pub fn new(id: impl ToString, name: impl ToString, version: impl ToString) -> Self {
+        FixtureFamily {
+            description: FontDescription::new(name, id, version),
+            files: BTreeMap::new(),
+            licenses: BTreeMap::new(),
+            unavailable: BTreeSet::new(),
+            tags: Vec::new()
+        }
+    }
+
+    pub fn with_file(mut self, variant: DefinedFontVariantSpec, extension: impl ToString, contents: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(variant, (extension.to_string(), contents.into()));
+        self
+    }
+
+    pub fn with_license(mut self, name: impl ToString, contents: impl Into<Vec<u8>>) -> Self {
+        self.licenses.insert(name.to_string(), contents.into());
+        self
+    }
+
+    /// Adds a variant that can be resolved, but not downloaded.
+    pub fn with_unavailable_variant(mut self, weight: u32, style: DefinedFontStyle) -> Self {
+        self.unavailable.insert(DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(weight), style });
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl ToString) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Adds a variant whose file contents describe the variant (e.g. `test-sans 700 italic`).
+    pub fn with_variant(self, weight: u32, style: DefinedFontStyle) -> Self {
+        let contents = format!("{} {} {}", self.description.id, weight, style);
+        let variant = DefinedFontVariantSpec { weight: DefinedFontWeight::Fixed(weight), style };
+        self.with_file(variant, "ttf", contents)
+    }
+}
+
+/// A source serving [`FixtureFamily`]s from memory.
+///
+/// Downloads are written to `<dir>/<font id>/<weight>-<style>.<extension>`, and licenses to `<dir>/<font id>/licenses/<name>`.
+pub struct InMemorySource<'host> {
+    id: String,
+    name: String,
+    families: BTreeMap<String, FixtureFamily>,
+    host: &'host dyn FpmHost,
+    refreshed: AtomicBool,
+    downloads: AtomicUsize
+}
+
+impl<'host> InMemorySource<'host> {
+    pub fn new(id: impl ToString, name: impl ToString) -> Self {
+        InMemorySource {
+            id: id.to_string(),
+            name: name.to_string(),
+            families: BTreeMap::new(),
+            host: &EmptyFpmHost::EMPTY_HOST,
+            refreshed: AtomicBool::new(false),
+            downloads: AtomicUsize::new(0)
+        }
+    }
+
+    pub fn with_family(mut self, family: FixtureFamily) -> Self {
+        self.add_family(family);
+        self
+    }
+    pub fn add_family(&mut self, family: FixtureFamily) {
+        self.families.insert(family.description.id.clone(), family);
+    }
+
+    /// The host set using [`Source::set_host`].
+    pub fn host(&self) -> &'host dyn FpmHost {
+        self.host
+    }
+
+    /// How many files have been written by [`Source::download_font`].
+    pub fn download_count(&self) -> usize {
+        self.downloads.load(Ordering::SeqCst)
+    }
+
+    fn family(&self, id: &str) -> Result<&FixtureFamily, Error> {
+        self.families.get(id).ok_or_else(|| Error::NoSuchFamily(id.to_string()))
+    }
+}
+
+fn file_name(variant: &DefinedFontVariantSpec, extension: &str) -> String {
+    format!("{}-{}.{}", variant.weight, variant.style, extension)
+}
+
+#[async_trait]
+impl<'host> Source<'host> for InMemorySource<'host> {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_host(&mut self, host: &'host dyn FpmHost) {
+        self.host = host;
+    }
+
+    /// Reports [`RefreshOutput::Downloaded`] the first time (or when forced), and [`RefreshOutput::AlreadyUpToDate`] after that.
+    async fn refresh(&self, force: bool) -> Result<RefreshOutput, Error> {
+        let already_refreshed = self.refreshed.swap(true, Ordering::SeqCst);
+        if already_refreshed && !force {
+            Ok(RefreshOutput::AlreadyUpToDate)
+        } else {
+            Ok(RefreshOutput::Downloaded)
+        }
+    }
+
+    async fn resolve_font(&self, spec: &FontInstallSpec) -> Result<(DefinedFontInstallSpec, FontDescription), Error> {
+        let family = self.family(&spec.id)?;
+        let styles: Vec<DefinedFontVariantSpec> = family.files.keys()
+            .chain(&family.unavailable)
+            .filter(|available| {
+                let available = FontVariantSpec {
+                    weight: FontWeight::Defined(available.weight),
+                    style: FontStyle::Defined(available.style)
+                };
+                spec.styles.iter().any(|requested| available.is_covered_by(requested))
+            })
+            .copied()
+            .collect();
+
+        Ok((DefinedFontInstallSpec::new(&spec.id, styles), family.description.clone()))
+    }
+
+    async fn download_font(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<DefinedFontVariantSpec, PathBuf>, Error> {
+        let family = self.family(&spec.id)?;
+        let dir = dir.join(&spec.id);
+        fs::create_dir_all(&dir)?;
+
+        let mut paths = HashMap::new();
+        for variant in &spec.styles {
+            if family.unavailable.contains(variant) {
+                return Err(Error::ConnectionError(format!("{} {} is unavailable", variant.weight, variant.style)))
+            }
+            let (extension, contents) = family.files.get(variant)
+                .ok_or_else(|| Error::Generic(format!("Could not get file for font variant {} {}", variant.weight, variant.style)))?;
+            let path = dir.join(file_name(variant, extension));
+            fs::write(&path, contents)?;
+            self.downloads.fetch_add(1, Ordering::SeqCst);
+            paths.insert(*variant, path);
+        }
+        Ok(paths)
+    }
+
+    async fn download_licenses(&self, spec: &DefinedFontInstallSpec, dir: &PathBuf) -> Result<HashMap<String, PathBuf>, Error> {
+        let family = self.family(&spec.id)?;
+        let dir = dir.join(&spec.id).join("licenses");
+
+        let mut paths = HashMap::new();
+        // stored by index, as some tests use names that wouldn't be safe as paths
+        for (i, (name, contents)) in family.licenses.iter().enumerate() {
+            fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("license-{}", i));
+            fs::write(&path, contents)?;
+            paths.insert(name.clone(), path);
+        }
+        Ok(paths)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<FontDescription>, Error> {
+        let query = query.to_lowercase();
+        Ok(self.families.values()
+            .map(|v| &v.description)
+            .filter(|v| v.id.contains(&query) || v.name.to_lowercase().contains(&query))
+            .cloned()
+            .collect())
+    }
+
+    /// Reports the total size of the file contents of each family.
+    async fn search_by_tags(&self, tags: &[String]) -> Result<Vec<TaggedFont>, Error> {
+        Ok(self.families.values()
+            .filter(|family| tags.iter().all(|tag| family.tags.contains(tag)))
+            .map(|family| TaggedFont {
+                font: family.description.clone(),
+                size: Some(family.files.values().map(|(_, contents)| contents.len() as u64).sum())
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{assert_file_contents, assert_installed};
+    use crate::TempFpmHost;
+
+    fn source<'host>() -> InMemorySource<'host> {
+        InMemorySource::new("memory", "In-Memory")
+            .with_family(FixtureFamily::new("test-sans", "Test Sans", "1")
+                .with_variant(400, DefinedFontStyle::Regular)
+                .with_variant(400, DefinedFontStyle::Italic)
+                .with_variant(700, DefinedFontStyle::Regular))
+    }
+
+    #[tokio::test]
+    async fn resolves_requested_styles() {
+        let source = source();
+        let (all, description) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+        assert_eq!(description.name, "Test Sans");
+        assert_eq!(all.styles.len(), 3);
+
+        let italic = FontVariantSpec { weight: FontWeight::All, style: FontStyle::Defined(DefinedFontStyle::Italic) };
+        let (italic, _) = source.resolve_font(&FontInstallSpec::new("test-sans", [italic])).await.unwrap();
+        assert_eq!(italic.styles, vec![DefinedFontVariantSpec { weight: DefinedFontWeight::REGULAR, style: DefinedFontStyle::Italic }]);
+
+        assert!(matches!(source.resolve_font(&FontInstallSpec::new_all_styles("nope")).await, Err(Error::NoSuchFamily(_))));
+    }
+
+    #[tokio::test]
+    async fn downloads_to_host_cache() {
+        let host = TempFpmHost::new();
+        let mut source = source();
+        source.set_host(&host);
+
+        let (spec, _) = source.resolve_font(&FontInstallSpec::new_all_styles("test-sans")).await.unwrap();
+        let cache_dir = host.cache_dir_for(source.id());
+        let paths = source.download_font(&spec, &cache_dir).await.unwrap();
+
+        assert_eq!(source.download_count(), 3);
+        assert_installed(&cache_dir, &["test-sans/400-italic.ttf", "test-sans/400-regular.ttf", "test-sans/700-regular.ttf"]);
+        assert_file_contents(&paths[&DefinedFontVariantSpec::REGULAR], "test-sans 400 regular");
+    }
+}